@@ -0,0 +1,480 @@
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr, time::Instant};
+
+#[cfg(feature = "alloc-stats")]
+use crate::alloc_stats;
+use crate::{
+    debugger::{self, Debugger},
+    image::ImageFormat,
+    problem_1, problem_10, problem_11, problem_12, problem_2, problem_3, problem_4, problem_5,
+    problem_6, problem_7, problem_8, problem_9,
+    recording::{self, Dumper, FrameFilter, FrameSink, Player, Simulation},
+    trace::{self, trace_event, LevelFilter},
+};
+
+pub const USAGE: &str = "\
+Usage:
+    aoc2022 run [<day>] [--part 1|2]
+    aoc2022 export <day> <output> [--format pgm|ppm|ansi] [--part 1|2]
+    aoc2022 record <day> [--part 1|2] [--from <step>] [--to <step>] [--every <steps>]
+                         [--fps <frames>] [--dump <directory>]
+    aoc2022 debug <day> [--part 1|2]
+    aoc2022 asm <file>
+    aoc2022 crt <file> [--width <pixels>] [--height <pixels>] [--sprite <pixels>]
+                       [--overflow stop|wrap|scroll] [--max-cycles <cycles>]
+    aoc2022 synth <text> [--seed <number>]
+    aoc2022 signal <file> <cycle>...
+    aoc2022 monkeys [--part 1|2] [--rounds <count>] [--relief <relief>] [--order fifo|lifo]
+                    [--metric product[:<count>]|sum|max] [--precision auto|u64|u128|big]
+                    [--method rounds|cycles]
+    aoc2022 report [--part 1|2] [--rounds <count>] [--relief <relief>] [--order fifo|lifo]
+                   [--after <round>,...] [--item <monkey>:<index>] [--format text|csv]
+    aoc2022 tree <file>
+    aoc2022 du <file> [--depth <levels>]
+    aoc2022 find <file> [--name <glob>] [--type f|d] [--min-size <bytes>]
+                        [--max-size <bytes>]
+    aoc2022 fs-json <file>
+    aoc2022 fs-transcript <file>
+
+Every command accepts --trace off|error|warn|info|debug|trace to print trace events
+on the standard error, which can also be enabled with the AOC_TRACE variable.
+
+Commands:
+    run       Print the solutions of a day, or of every day. When built with the
+              alloc-stats feature, also report the allocations of each solution.
+    export    Write the final state of day 8, 9 or 12 as an image. The format is
+              deduced from the output extension when --format is not given.
+    record    Record a text frame per step of day 5, 9, 10 or 11 and play them in
+              the terminal (10 frames per second by default) or dump them as files.
+    debug     Step interactively through day 5, 9, 10 or 11, reading commands from
+              the standard input.
+    asm       Assemble a day 10 CPU program and print it back in canonical form.
+    crt       Run a day 10 CPU program and print the CRT. The screen is 40x6 with a
+              3 pixel sprite by default, and stops drawing once full. Programs
+              still running after --max-cycles (1000000 by default) are stopped
+              with an error.
+    synth     Print a day 10 CPU program of addx and noop drawing up to 8 capital
+              letters on the CRT. Different seeds give different programs.
+    signal    Print X during each of the given cycles and the sum of their signal
+              strengths, running a day 10 CPU program while it is read, so it can
+              be of any length. Jumps are not supported.
+    monkeys   Print the day 11 monkey business. The rules of the part (20 rounds
+              dividing by 3 for part 1, 10000 rounds modulo the lcm of the divisors
+              for part 2, FIFO order and the product of the top 2) can be changed:
+              the relief is none, divide:<k>, modulo (by the lcm) or modulo:<k>,
+              and the metric is the product of the top counts, their sum or their
+              max. Worry levels never wrap: by default the smallest integer type
+              that does not overflow is used, and big gives exact results at any
+              size. With --method cycles and a modulo relief, each item is followed
+              until its state repeats, so any number of rounds can be played.
+    report    Print the day 11 inspections of each monkey during each round, the
+              items held after the --after rounds (0 for the start) and every
+              inspection of the --item starting item, as text or CSV. The rules
+              are changed as for monkeys.
+    tree      Print the filesystem of a day 7 terminal transcript as a tree, with the
+              size of every file and the total size of every directory. Problems in
+              the transcript are printed on the standard error, and the lines with
+              errors are skipped.
+    du        Print the total size of the directories of a day 7 transcript, the
+              largest first, down to --depth levels below the root.
+    find      Print the paths of the files and directories of a day 7 transcript
+              matching all the conditions: a name glob with * and ?, a type and a
+              size range (total size for directories).
+    fs-json   Print the filesystem of a day 7 transcript as JSON, with the content
+              of every directory nested in it.
+    fs-transcript
+              Print a day 7 transcript listing every directory of a filesystem
+              exported as JSON.";
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut options = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Missing value for option --{}", name))?;
+                    options.insert(name.to_owned(), value.clone());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Missing argument <{}>\n\n{}", name, USAGE))
+    }
+
+    fn parse_positional<T: FromStr>(&self, index: usize, name: &str) -> Result<T, String> {
+        let value = self.positional(index, name)?;
+        value
+            .parse()
+            .map_err(|_| format!("Invalid value {} for <{}>", value, name))
+    }
+
+    fn option<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.options
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid value {} for option --{}", value, name))
+            })
+            .transpose()
+    }
+}
+
+type Solution = fn() -> String;
+
+const SOLUTIONS: [(u32, u32, Solution); 24] = [
+    (1, 1, || problem_1::solution_part_1().to_string()),
+    (1, 2, || problem_1::solution_part_2().to_string()),
+    (2, 1, || problem_2::solution_part_1().to_string()),
+    (2, 2, || problem_2::solution_part_2().to_string()),
+    (3, 1, || problem_3::solution_part_1().to_string()),
+    (3, 2, || problem_3::solution_part_2().to_string()),
+    (4, 1, || problem_4::solution_part_1().to_string()),
+    (4, 2, || problem_4::solution_part_2().to_string()),
+    (5, 1, problem_5::solution_part_1),
+    (5, 2, problem_5::solution_part_2),
+    (6, 1, || problem_6::solution_part_1().to_string()),
+    (6, 2, || problem_6::solution_part_2().to_string()),
+    (7, 1, || problem_7::solution_part_1().to_string()),
+    (7, 2, || problem_7::solution_part_2().to_string()),
+    (8, 1, || problem_8::solution_part_1().to_string()),
+    (8, 2, || problem_8::solution_part_2().to_string()),
+    (9, 1, || problem_9::solution_part_1().to_string()),
+    (9, 2, || problem_9::solution_part_2().to_string()),
+    (10, 1, || problem_10::solution_part_1().to_string()),
+    (10, 2, || {
+        problem_10::solution_part_2_letters()
+            .unwrap_or_else(|_| format!("\n{}", problem_10::solution_part_2()))
+    }),
+    (11, 1, || problem_11::solution_part_1().to_string()),
+    (11, 2, || problem_11::solution_part_2().to_string()),
+    (12, 1, || problem_12::solution_part_1().to_string()),
+    (12, 2, || problem_12::solution_part_2().to_string()),
+];
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args)?;
+    if let Some(filter) = args.option::<LevelFilter>("trace")? {
+        trace::set_max_level(filter);
+    }
+    match args.positional.first().map(String::as_str) {
+        Some("run") => run_solutions(&args),
+        Some("export") => export(&args),
+        Some("record") => record(&args),
+        Some("debug") => debug(&args),
+        Some("asm") => asm(&args),
+        Some("crt") => crt(&args),
+        Some("synth") => synth(&args),
+        Some("signal") => signal(&args),
+        Some("monkeys") => monkeys(&args),
+        Some("report") => report(&args),
+        Some("tree") => fs_query(&args, problem_7::Query::Tree),
+        Some("du") => du(&args),
+        Some("find") => find(&args),
+        Some("fs-json") => fs_query(&args, problem_7::Query::Json),
+        Some("fs-transcript") => fs_transcript(&args),
+        Some(command) => Err(format!("Invalid command {}\n\n{}", command, USAGE)),
+        None => Err(USAGE.to_owned()),
+    }
+}
+
+fn run_solutions(args: &Args) -> Result<(), String> {
+    let day: Option<u32> = match args.positional.get(1) {
+        Some(_) => Some(args.parse_positional(1, "day")?),
+        None => None,
+    };
+    let part: Option<u32> = args.option("part")?;
+    let selected: Vec<_> = SOLUTIONS
+        .iter()
+        .filter(|(d, p, _)| day.is_none_or(|day| day == *d) && part.is_none_or(|part| part == *p))
+        .collect();
+    if selected.is_empty() {
+        return Err("No solution matches the given day and part".to_owned());
+    }
+    #[cfg(feature = "alloc-stats")]
+    let mut report = vec![];
+    for (day, part, solution) in selected {
+        let start = Instant::now();
+        #[cfg(feature = "alloc-stats")]
+        let (answer, stats) = alloc_stats::measure(solution);
+        #[cfg(not(feature = "alloc-stats"))]
+        let answer = solution();
+        trace_event!(
+            Info,
+            "solution computed",
+            day = day,
+            part = part,
+            elapsed_us = start.elapsed().as_micros()
+        );
+        println!("day {} part {}: {}", day, part, answer);
+        #[cfg(feature = "alloc-stats")]
+        report.push((day, part, stats));
+    }
+    #[cfg(feature = "alloc-stats")]
+    {
+        println!(
+            "\n{:>3} {:>4} {:>12} {:>16} {:>16}",
+            "day", "part", "allocations", "allocated bytes", "peak live bytes"
+        );
+        for (day, part, stats) in report {
+            println!(
+                "{:>3} {:>4} {:>12} {:>16} {:>16}",
+                day, part, stats.allocations, stats.allocated_bytes, stats.peak_live_bytes
+            );
+        }
+    }
+    Ok(())
+}
+
+fn export(args: &Args) -> Result<(), String> {
+    let day: u32 = args.parse_positional(1, "day")?;
+    let path = args.positional(2, "output")?;
+    let part: u32 = args.option("part")?.unwrap_or(1);
+    let format = match args.option::<ImageFormat>("format")? {
+        Some(format) => format,
+        None => ImageFormat::from_extension(path)
+            .ok_or_else(|| format!("Cannot deduce the image format of {}", path))?,
+    };
+    let written = match (day, part) {
+        (8, _) => problem_8::export_image(path, format),
+        (9, 1) => problem_9::export_image::<1>(path, format),
+        (9, 2) => problem_9::export_image::<9>(path, format),
+        (12, _) => problem_12::export_image(path, format),
+        _ => return Err(format!("No image export for day {} part {}", day, part)),
+    };
+    written.map_err(|e| format!("Cannot write {}: {}", path, e))
+}
+
+fn record(args: &Args) -> Result<(), String> {
+    let day: u32 = args.parse_positional(1, "day")?;
+    let part: u32 = args.option("part")?.unwrap_or(1);
+    let filter = FrameFilter::new(
+        args.option("from")?.unwrap_or(0),
+        args.option("to")?,
+        args.option("every")?.unwrap_or(1),
+    )?;
+    let recorded = match args.option::<String>("dump")? {
+        Some(directory) => {
+            let mut dumper = Dumper::new(Path::new(&directory));
+            record_day(day, part, &filter, &mut dumper).map(|recorded| {
+                recorded.map_err(|e| format!("Cannot dump frames to {}: {}", directory, e))
+            })
+        }
+        None => {
+            let fps: f64 = args.option("fps")?.unwrap_or(10.0);
+            if fps <= 0.0 {
+                return Err("The --fps option must be positive".to_owned());
+            }
+            let mut player = Player::new(fps, io::stdout());
+            record_day(day, part, &filter, &mut player)
+                .map(|recorded| recorded.map_err(|e| e.to_string()))
+        }
+    };
+    recorded.unwrap_or_else(|| Err(format!("No recording for day {} part {}", day, part)))
+}
+
+// `None` when the day and part have no simulation.
+fn record_day(
+    day: u32,
+    part: u32,
+    filter: &FrameFilter,
+    sink: &mut impl FrameSink,
+) -> Option<io::Result<()>> {
+    Some(match (day, part) {
+        (5, _) => recording::record(&mut problem_5::simulation(part == 2), filter, sink),
+        (9, 1) => recording::record(&mut problem_9::simulation::<1>(), filter, sink),
+        (9, 2) => recording::record(&mut problem_9::simulation::<9>(), filter, sink),
+        (10, _) => recording::record(&mut problem_10::simulation(), filter, sink),
+        (11, 1) => recording::record(&mut problem_11::simulation_part_1(), filter, sink),
+        (11, 2) => recording::record(&mut problem_11::simulation_part_2(), filter, sink),
+        _ => return None,
+    })
+}
+
+fn debug_simulation(simulation: impl Simulation + Clone) -> Result<(), String> {
+    let mut debugger = Debugger::new(simulation);
+    debugger::run_interactive(&mut debugger, io::stdin().lock(), &mut io::stdout())
+        .map_err(|e| e.to_string())
+}
+
+fn debug(args: &Args) -> Result<(), String> {
+    let day: u32 = args.parse_positional(1, "day")?;
+    let part: u32 = args.option("part")?.unwrap_or(1);
+    match (day, part) {
+        (5, _) => debug_simulation(problem_5::simulation(part == 2)),
+        (9, 1) => debug_simulation(problem_9::simulation::<1>()),
+        (9, 2) => debug_simulation(problem_9::simulation::<9>()),
+        (10, _) => debug_simulation(problem_10::simulation()),
+        (11, 1) => debug_simulation(problem_11::simulation_part_1()),
+        (11, 2) => debug_simulation(problem_11::simulation_part_2()),
+        _ => Err(format!("No debugger for day {} part {}", day, part)),
+    }
+}
+
+fn asm(args: &Args) -> Result<(), String> {
+    let path = args.positional(1, "file")?;
+    let source = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let text = problem_10::canonicalize(&source).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", text);
+    Ok(())
+}
+
+fn crt(args: &Args) -> Result<(), String> {
+    let path = args.positional(1, "file")?;
+    let source = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let config = problem_10::CrtConfig::new(
+        args.option("width")?.unwrap_or(40),
+        args.option("height")?.unwrap_or(6),
+        args.option("sprite")?.unwrap_or(3),
+        &args
+            .option::<String>("overflow")?
+            .unwrap_or("stop".to_owned()),
+    )?;
+    let max_cycles = args.option("max-cycles")?.unwrap_or(problem_10::MAX_CYCLES);
+    let screen =
+        problem_10::render(&source, config, max_cycles).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", screen);
+    Ok(())
+}
+
+fn synth(args: &Args) -> Result<(), String> {
+    let text = args.positional(1, "text")?;
+    let program = problem_10::synthesize(text, args.option("seed")?.unwrap_or(0))?;
+    print!("{}", program);
+    Ok(())
+}
+
+fn signal(args: &Args) -> Result<(), String> {
+    let path = args.positional(1, "file")?;
+    args.positional(2, "cycle")?;
+    let cycles = (2..args.positional.len())
+        .map(|i| args.parse_positional(i, "cycle"))
+        .collect::<Result<Vec<usize>, _>>()?;
+    let file = fs::File::open(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let (values, total) = problem_10::stream_signal(io::BufReader::new(file), &cycles)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    for (cycle, x) in cycles.iter().zip(values) {
+        println!("cycle {}: X={}", cycle, x);
+    }
+    println!("signal strength: {}", total);
+    Ok(())
+}
+
+fn rule_overrides(args: &Args) -> Result<problem_11::RuleOverrides, String> {
+    Ok(problem_11::RuleOverrides {
+        rounds: args.option("rounds")?,
+        relief: args.option("relief")?,
+        order: args.option("order")?,
+        metric: args.option("metric")?,
+    })
+}
+
+fn monkeys(args: &Args) -> Result<(), String> {
+    let part = args.option("part")?.unwrap_or(1);
+    let overrides = rule_overrides(args)?;
+    match args.option::<String>("method")?.as_deref() {
+        None | Some("rounds") => (),
+        Some("cycles") => {
+            let business = problem_11::monkey_business_with_cycles(part, &overrides)?;
+            println!("monkey business: {}", business);
+            return Ok(());
+        }
+        Some(method) => return Err(format!("Invalid method {}", method)),
+    }
+    let representation = match args.option::<String>("precision")?.as_deref() {
+        None | Some("auto") => None,
+        Some(precision) => Some(precision.parse()?),
+    };
+    let (business, representation) = problem_11::monkey_business(part, &overrides, representation)?;
+    println!("monkey business: {} ({})", business, representation);
+    Ok(())
+}
+
+fn report(args: &Args) -> Result<(), String> {
+    let snapshots = match args.option::<String>("after")? {
+        Some(rounds) => rounds
+            .split(',')
+            .map(|round| {
+                round
+                    .parse()
+                    .map_err(|_| format!("Invalid round {} for --after", round))
+            })
+            .collect::<Result<_, _>>()?,
+        None => vec![],
+    };
+    let item = match args.option::<String>("item")? {
+        Some(item) => {
+            let error = || format!("Invalid item {}, expected <monkey>:<index>", item);
+            let (monkey, index) = item.split_once(':').ok_or_else(error)?;
+            Some((
+                monkey.parse().map_err(|_| error())?,
+                index.parse().map_err(|_| error())?,
+            ))
+        }
+        None => None,
+    };
+    let text = problem_11::report(
+        args.option("part")?.unwrap_or(1),
+        &rule_overrides(args)?,
+        snapshots,
+        item,
+        args.option("format")?
+            .unwrap_or(problem_11::ReportFormat::Text),
+    )?;
+    print!("{}", text);
+    Ok(())
+}
+
+fn fs_query(args: &Args, query: problem_7::Query) -> Result<(), String> {
+    let path = args.positional(1, "file")?;
+    let transcript =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let (output, diagnostics) = problem_7::query(&transcript, &query);
+    for diagnostic in diagnostics {
+        eprintln!("{}: {}", path, diagnostic);
+    }
+    print!("{}", output);
+    Ok(())
+}
+
+fn du(args: &Args) -> Result<(), String> {
+    let max_depth = args.option("depth")?;
+    fs_query(args, problem_7::Query::Du { max_depth })
+}
+
+fn find(args: &Args) -> Result<(), String> {
+    let query = problem_7::FindQuery {
+        name: args.option("name")?,
+        kind: args.option("type")?,
+        min_size: args.option("min-size")?,
+        max_size: args.option("max-size")?,
+    };
+    fs_query(args, problem_7::Query::Find(query))
+}
+
+fn fs_transcript(args: &Args) -> Result<(), String> {
+    let path = args.positional(1, "file")?;
+    let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let transcript =
+        problem_7::transcript_from_json(&json).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", transcript);
+    Ok(())
+}
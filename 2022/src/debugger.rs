@@ -0,0 +1,208 @@
+#![allow(dead_code)]
+
+use std::io::{self, BufRead, Write};
+
+use crate::recording::Simulation;
+
+// Only one state every CHECKPOINT_INTERVAL steps is kept; going back replays the
+// simulation from the closest checkpoint.
+const CHECKPOINT_INTERVAL: usize = 100;
+
+const HELP: &str = "\
+Commands:
+    n [count]    step forward (an empty line steps once)
+    b [count]    step backward
+    g <step>     go to step
+    f            print the current frame
+    i            inspect the full state
+    h            print this help
+    q            quit";
+
+pub struct Debugger<S: Simulation + Clone> {
+    checkpoints: Vec<S>,
+    current: S,
+    step: usize,
+    last_step: Option<usize>,
+}
+
+impl<S: Simulation + Clone> Debugger<S> {
+    pub fn new(simulation: S) -> Self {
+        Self {
+            checkpoints: vec![simulation.clone()],
+            current: simulation,
+            step: 0,
+            last_step: None,
+        }
+    }
+
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn state(&self) -> &S {
+        &self.current
+    }
+
+    pub fn forward(&mut self) -> bool {
+        if self.last_step == Some(self.step) {
+            return false;
+        }
+        if !self.current.step() {
+            self.last_step = Some(self.step);
+            return false;
+        }
+        self.step += 1;
+        if self.step.is_multiple_of(CHECKPOINT_INTERVAL)
+            && self.checkpoints.len() == self.step / CHECKPOINT_INTERVAL
+        {
+            self.checkpoints.push(self.current.clone());
+        }
+        true
+    }
+
+    pub fn backward(&mut self) -> bool {
+        if self.step == 0 {
+            return false;
+        }
+        self.goto(self.step - 1);
+        true
+    }
+
+    // Stops early if the simulation ends before `target`. Returns the step reached.
+    pub fn goto(&mut self, target: usize) -> usize {
+        if target < self.step {
+            let checkpoint = (target / CHECKPOINT_INTERVAL).min(self.checkpoints.len() - 1);
+            self.current = self.checkpoints[checkpoint].clone();
+            self.step = checkpoint * CHECKPOINT_INTERVAL;
+        }
+        while self.step < target && self.forward() {}
+        self.step
+    }
+}
+
+fn parse_count(argument: Option<&str>) -> Result<usize, String> {
+    argument.map_or(Ok(1), |n| {
+        n.parse().map_err(|_| format!("Invalid number {}", n))
+    })
+}
+
+pub fn run_interactive<S: Simulation + Clone>(
+    debugger: &mut Debugger<S>,
+    input: impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "{}\n\nstep 0\n{}", HELP, debugger.state().frame())?;
+    write!(out, "> ")?;
+    out.flush()?;
+    for ln in input.lines() {
+        let ln = ln?;
+        let mut words = ln.split_whitespace();
+        let command = words.next().unwrap_or("n");
+        let argument = words.next();
+        let start = debugger.step();
+        let moved = match command {
+            "n" => parse_count(argument).map(|count| {
+                (0..count).take_while(|_| debugger.forward()).count();
+                true
+            }),
+            "b" => parse_count(argument).map(|count| {
+                debugger.goto(start.saturating_sub(count));
+                true
+            }),
+            "g" => match argument.map(str::parse::<usize>) {
+                Some(Ok(target)) => {
+                    debugger.goto(target);
+                    Ok(true)
+                }
+                _ => Err("Usage: g <step>".to_owned()),
+            },
+            "f" => Ok(true),
+            "i" => {
+                writeln!(out, "{}", debugger.state().inspect())?;
+                Ok(false)
+            }
+            "h" => {
+                writeln!(out, "{}", HELP)?;
+                Ok(false)
+            }
+            "q" => return Ok(()),
+            _ => Err(format!("Invalid command {}, type h for help", command)),
+        };
+        match moved {
+            Ok(true) => {
+                writeln!(
+                    out,
+                    "step {}\n{}",
+                    debugger.step(),
+                    debugger.state().frame()
+                )?;
+                if debugger.last_step == Some(debugger.step()) {
+                    writeln!(out, "(end of simulation)")?;
+                }
+            }
+            Ok(false) => {}
+            Err(e) => writeln!(out, "{}", e)?,
+        }
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::*;
+
+    #[derive(Clone)]
+    struct Counter {
+        value: usize,
+        limit: usize,
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            if self.value == self.limit {
+                return false;
+            }
+            self.value += 1;
+            true
+        }
+
+        fn frame(&self) -> String {
+            self.value.to_string()
+        }
+    }
+
+    #[test]
+    fn debugger_navigation_test() {
+        let mut debugger = Debugger::new(Counter {
+            value: 0,
+            limit: 250,
+        });
+        assert_eq!(debugger.goto(230), 230);
+        assert!(debugger.backward());
+        assert_eq!(debugger.state().value, 229);
+        assert_eq!(debugger.goto(5), 5);
+        assert_eq!(debugger.state().value, 5);
+        assert_eq!(debugger.goto(1000), 250);
+        assert!(!debugger.forward());
+        assert_eq!(debugger.checkpoints.len(), 3);
+    }
+
+    #[test]
+    fn debugger_run_interactive_test() {
+        let mut debugger = Debugger::new(Counter { value: 0, limit: 9 });
+        let mut out = vec![];
+        run_interactive(
+            &mut debugger,
+            "n 3\n\nb\ng 7\nx\nq\nn\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(debugger.step(), 7);
+        assert!(out.contains("step 4\n4\n"));
+        assert!(out.contains("step 3\n3\n"));
+        assert!(out.contains("Invalid command x"));
+    }
+}
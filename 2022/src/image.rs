@@ -0,0 +1,161 @@
+#![allow(dead_code)]
+
+use std::{fmt::Write, fs, io, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const RED: Rgb = Rgb(255, 0, 0);
+    pub const GREEN: Rgb = Rgb(0, 255, 0);
+    pub const BLUE: Rgb = Rgb(0, 0, 255);
+
+    pub fn gray(level: u8) -> Self {
+        Rgb(level, level, level)
+    }
+
+    // Integer approximation of the ITU-R BT.601 luma.
+    fn luminance(&self) -> u8 {
+        let Rgb(r, g, b) = *self;
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Pgm,
+    Ppm,
+    Ansi,
+}
+
+impl ImageFormat {
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        extension.parse().ok()
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(Self::Pgm),
+            "ppm" => Ok(Self::Ppm),
+            "ansi" | "ans" => Ok(Self::Ansi),
+            _ => Err(format!("Invalid image format {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(height: usize, width: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, color: Rgb) {
+        self.pixels[row * self.width + col] = color;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Rgb {
+        self.pixels[row * self.width + col]
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Rgb]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    // Plain (ASCII) netpbm variants, so that exported files can be diffed.
+    pub fn to_pgm(&self) -> String {
+        let mut out = format!("P2\n{} {}\n255\n", self.width, self.height);
+        for row in self.rows() {
+            let line: Vec<_> = row.iter().map(|p| p.luminance().to_string()).collect();
+            out += &line.join(" ");
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_ppm(&self) -> String {
+        let mut out = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for row in self.rows() {
+            let line: Vec<_> = row
+                .iter()
+                .map(|Rgb(r, g, b)| format!("{} {} {}", r, g, b))
+                .collect();
+            out += &line.join(" ");
+            out.push('\n');
+        }
+        out
+    }
+
+    // Every pixel is drawn as two blank cells with a 24-bit background color.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            for Rgb(r, g, b) in row {
+                write!(out, "\x1b[48;2;{};{};{}m  ", r, g, b).unwrap();
+            }
+            out += "\x1b[0m\n";
+        }
+        out
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> String {
+        match format {
+            ImageFormat::Pgm => self.to_pgm(),
+            ImageFormat::Ppm => self.to_ppm(),
+            ImageFormat::Ansi => self.to_ansi(),
+        }
+    }
+
+    pub fn save(&self, path: &str, format: ImageFormat) -> io::Result<()> {
+        fs::write(path, self.encode(format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::*;
+
+    fn checkerboard() -> Image {
+        let mut image = Image::new(2, 3, Rgb::BLACK);
+        image.set(0, 0, Rgb::WHITE);
+        image.set(1, 1, Rgb::RED);
+        image
+    }
+
+    #[test]
+    fn image_netpbm_test() {
+        let image = checkerboard();
+        assert_eq!(image.to_pgm(), "P2\n3 2\n255\n255 0 0\n0 76 0\n");
+        assert_eq!(
+            image.to_ppm(),
+            "P3\n3 2\n255\n255 255 255 0 0 0 0 0 0\n0 0 0 255 0 0 0 0 0\n"
+        );
+    }
+
+    #[test]
+    fn image_ansi_test() {
+        let ansi = checkerboard().to_ansi();
+        assert_eq!(ansi.lines().count(), 2);
+        assert!(ansi.starts_with("\x1b[48;2;255;255;255m  \x1b[48;2;0;0;0m  "));
+        assert_eq!(
+            ImageFormat::from_extension("out/trees.ppm"),
+            Some(ImageFormat::Ppm)
+        );
+    }
+}
@@ -1,3 +1,8 @@
+#[cfg(feature = "alloc-stats")]
+mod alloc_stats;
+pub mod cli;
+mod debugger;
+mod image;
 mod problem_1;
 mod problem_10;
 mod problem_11;
@@ -10,4 +15,6 @@ mod problem_6;
 mod problem_7;
 mod problem_8;
 mod problem_9;
+mod recording;
+mod trace;
 mod utilities;
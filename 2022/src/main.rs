@@ -1,11 +1,24 @@
+use std::{env, process};
+
+use aoc2022::cli;
+
 fn main() {
-    println!(
-        r#"
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        println!(
+            r#"
              _                 _            __    _____          _        ___   ___ ___  ___  
     /\      | |               | |          / _|  / ____|        | |      |__ \ / _ \__ \|__ \ 
    /  \   __| |_   _____ _ __ | |_    ___ | |_  | |     ___   __| | ___     ) | | | | ) |  ) |
   / /\ \ / _` \ \ / / _ \ '_ \| __|  / _ \|  _| | |    / _ \ / _` |/ _ \   / /| | | |/ /  / / 
  / ____ \ (_| |\ V /  __/ | | | |_  | (_) | |   | |___| (_) | (_| |  __/  / /_| |_| / /_ / /_ 
 /_/    \_\__,_| \_/ \___|_| |_|\__|  \___/|_|    \_____\___/ \__,_|\___| |____|\___/____|____|"#
-    );
+        );
+        println!("\n{}", cli::USAGE);
+        return;
+    }
+    if let Err(e) = cli::run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
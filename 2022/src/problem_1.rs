@@ -18,12 +18,12 @@ fn get_calories_per_elf(input: String) -> Vec<usize> {
         .collect()
 }
 
-fn solution_part_1() -> usize {
+pub fn solution_part_1() -> usize {
     let content = get_input();
-    get_calories_per_elf(content).iter().max().unwrap().clone()
+    *get_calories_per_elf(content).iter().max().unwrap()
 }
 
-fn solution_part_2() -> usize {
+pub fn solution_part_2() -> usize {
     let content = get_input();
     let mut total_calories_per_elf = get_calories_per_elf(content);
     total_calories_per_elf.sort_by(|a, b| b.cmp(a));
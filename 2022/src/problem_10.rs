@@ -1,6 +1,21 @@
 #![allow(dead_code)]
 
-use crate::utilities::read_file;
+mod assembler;
+mod isa;
+mod monitor;
+mod observers;
+mod ocr;
+mod stream;
+mod synthesizer;
+
+use std::{fmt, io, str::FromStr};
+
+use crate::{recording::Simulation, trace::trace_event, utilities::read_file};
+
+use assembler::{assemble, disassemble};
+use isa::{Instruction, Register, REGISTERS};
+use observers::{CycleObserver, Schedule, SignalStrength};
+use ocr::{recognize, OcrConfig, OcrError};
 
 fn get_input() -> String {
     // read_file("problem_10_sample")
@@ -8,138 +23,375 @@ fn get_input() -> String {
 }
 
 fn parse_input() -> Vec<Instruction> {
-    get_input()
-        .lines()
-        .map(|ln| {
-            if ln.starts_with("noop") {
-                Instruction::Noop
-            } else {
-                let n: i32 = ln.split_once(' ').unwrap().1.parse().unwrap();
-                Instruction::Addx(n)
-            }
-        })
-        .collect()
+    assemble(&get_input()).unwrap_or_else(|e| panic!("Invalid program, {}", e))
 }
 
-enum Instruction {
-    Noop,
-    Addx(i32),
+// Assembles a program and writes it back as canonical text.
+pub fn canonicalize(source: &str) -> Result<String, String> {
+    assemble(source)
+        .map(|program| disassemble(&program))
+        .map_err(|e| e.to_string())
 }
 
-const SCREEN_HEIGHT: usize = 6;
-const SCREEN_WIDTH: usize = 40;
+// Cycles a program may run by default before it is considered stuck in a loop
+pub const MAX_CYCLES: usize = 1_000_000;
 
-type Screen = [[char; SCREEN_WIDTH]; SCREEN_HEIGHT];
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleLimitExceeded {
+    pub limit: usize,
+}
 
-struct Crt {
-    pixels: Screen,
-    sprite_center: i32,
-    row: usize,
-    col: usize,
+impl fmt::Display for CycleLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the program did not halt within {} cycles", self.limit)
+    }
 }
 
-impl Crt {
-    fn new() -> Self {
-        Self {
-            pixels: [[' '; SCREEN_WIDTH]; SCREEN_HEIGHT],
-            sprite_center: 1,
-            row: 0,
-            col: 0,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Overflow {
+    // Pixels past the last row are not drawn
+    Stop,
+    // Drawing restarts from the first row
+    Wrap,
+    // Rows move up to make room for a new last row
+    Scroll,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(Self::Stop),
+            "wrap" => Ok(Self::Wrap),
+            "scroll" => Ok(Self::Scroll),
+            _ => Err(format!("Invalid overflow {}", s)),
         }
     }
+}
 
-    fn set_sprite_center(&mut self, center: i32) {
-        self.sprite_center = center;
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrtConfig {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+    overflow: Overflow,
+}
 
-    fn draw_pixel(&mut self) {
-        if self.is_pixel_visible() {
-            self.pixels[self.row][self.col] = '#';
+impl Default for CrtConfig {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            overflow: Overflow::Stop,
         }
-        if self.col == SCREEN_WIDTH - 1 {
-            self.col = 0;
-            self.row += 1;
-        } else {
-            self.col += 1;
+    }
+}
+
+impl CrtConfig {
+    pub fn new(
+        width: usize,
+        height: usize,
+        sprite_width: usize,
+        overflow: &str,
+    ) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("The screen must have at least one pixel".to_owned());
         }
+        Ok(Self {
+            width,
+            height,
+            sprite_width,
+            overflow: overflow.parse()?,
+        })
+    }
+
+    // The pixel drawn during `cycle` (1 based) with the sprite centered on `x`, none
+    // once a stopping screen is full. Past the last row, a wrapping screen goes back
+    // to its first row and a scrolling one stays on its last row.
+    fn pixel(&self, cycle: usize, x: i32) -> Option<Pixel> {
+        let (row, col) = ((cycle - 1) / self.width, (cycle - 1) % self.width);
+        let row = match self.overflow {
+            _ if row < self.height => row,
+            Overflow::Stop => return None,
+            Overflow::Wrap => row % self.height,
+            Overflow::Scroll => self.height - 1,
+        };
+        Some(Pixel {
+            row,
+            col,
+            lit: self.is_lit(col, x),
+        })
     }
 
-    fn is_pixel_visible(&self) -> bool {
-        let col = self.col as i32;
-        (col >= self.sprite_center && col - self.sprite_center <= 1)
-            || (col < self.sprite_center && self.sprite_center - col == 1)
+    // The sprite spans `sprite_width` pixels around its center, with the extra pixel
+    // on the right for even widths.
+    fn is_lit(&self, col: usize, sprite_center: i32) -> bool {
+        let offset = col as i64 - sprite_center as i64;
+        let width = self.sprite_width as i64;
+        width > 0 && -(width - 1) / 2 <= offset && offset <= width / 2
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pixel {
+    row: usize,
+    col: usize,
+    lit: bool,
+}
+
+// Draws the pixels it observes. It must see every cycle from the first one.
+#[derive(Clone)]
+struct Crt {
+    config: CrtConfig,
+    pixels: Vec<Vec<char>>,
+}
+
+impl Crt {
+    fn new(config: CrtConfig) -> Self {
+        Self {
+            config,
+            pixels: vec![vec![' '; config.width]; config.height],
+        }
     }
 
     fn display(&self) -> String {
-        self.pixels.into_iter().fold("".to_owned(), |acc, row| {
+        self.pixels.iter().fold("".to_owned(), |acc, row| {
             let r = row.iter().collect::<String>();
             acc + &r + "\n"
         })
     }
 }
 
+impl CycleObserver for Crt {
+    fn observe(&mut self, cycle: usize, _x: i32, pixel: Option<Pixel>) {
+        let Some(Pixel { row, col, lit }) = pixel else {
+            return;
+        };
+        let CrtConfig { width, height, .. } = self.config;
+        // A scrolling screen makes room for each row past the last one
+        if self.config.overflow == Overflow::Scroll && col == 0 && cycle > width * height {
+            self.pixels.remove(0);
+            self.pixels.push(vec![' '; width]);
+        }
+        self.pixels[row][col] = if lit { '#' } else { ' ' };
+    }
+}
+
+// The screen geometry only tells observers which pixel each cycle draws.
+#[derive(Clone)]
 struct Cpu {
-    reg: i32,
-    cycles: Vec<i32>,
-    crt: Crt,
+    registers: [i32; REGISTERS],
+    // Cycles run so far
+    cycles: usize,
+    screen: CrtConfig,
 }
 
 impl Cpu {
     fn new() -> Self {
+        Self::with_screen(CrtConfig::default())
+    }
+
+    fn with_screen(screen: CrtConfig) -> Self {
+        let mut registers = [0; REGISTERS];
+        registers[Register::X.index()] = 1;
         Self {
-            reg: 1,
-            cycles: vec![],
-            crt: Crt::new(),
+            registers,
+            cycles: 0,
+            screen,
         }
     }
 
-    fn cycle(&mut self, times: u32) {
+    fn get(&self, register: Register) -> i32 {
+        self.registers[register.index()]
+    }
+
+    fn x(&self) -> i32 {
+        self.get(Register::X)
+    }
+
+    // The pixel drawn by the next cycle
+    fn next_pixel(&self) -> Option<Pixel> {
+        self.screen.pixel(self.cycles + 1, self.x())
+    }
+
+    fn cycle(&mut self, times: u32, observer: &mut dyn CycleObserver) {
         for _ in 0..times {
-            self.cycles.push(self.reg);
-            self.crt.draw_pixel();
+            let pixel = self.next_pixel();
+            self.cycles += 1;
+            observer.observe(self.cycles, self.x(), pixel);
         }
     }
 
-    fn execute(&mut self, instruction: Instruction) {
-        match instruction {
-            Instruction::Noop => self.cycle(1),
-            Instruction::Addx(x) => {
-                self.cycle(2);
-                self.reg += x;
-                self.crt.set_sprite_center(self.reg);
-            }
-        };
+    // Returns the offset to add to the program counter.
+    fn execute(&mut self, instruction: Instruction, observer: &mut dyn CycleObserver) -> i32 {
+        trace_event!(
+            Trace,
+            "instruction executed",
+            cycle = self.cycles + 1,
+            x = self.x(),
+            instruction = instruction
+        );
+        self.cycle(instruction.cycles(), observer);
+        self.apply(instruction)
     }
 
-    fn signal_strength(&mut self, cycle_nr: usize) -> i32 {
-        self.cycles[cycle_nr - 1] * (cycle_nr as i32)
+    // Applies the effects of an instruction whose cycles have elapsed. Returns the
+    // offset to add to the program counter.
+    fn apply(&mut self, instruction: Instruction) -> i32 {
+        instruction.execute(&mut self.registers)
     }
+
+    // Runs until the program counter leaves the program. An instruction that would go
+    // past `max_cycles` is not started.
+    fn run(
+        &mut self,
+        program: &[Instruction],
+        max_cycles: usize,
+        observer: &mut dyn CycleObserver,
+    ) -> Result<(), CycleLimitExceeded> {
+        let mut pc = 0;
+        while let Some(&instruction) = program.get(pc) {
+            if self.cycles + instruction.cycles() as usize > max_cycles {
+                return Err(CycleLimitExceeded { limit: max_cycles });
+            }
+            match jump(pc, self.execute(instruction, observer)) {
+                Some(next) => pc = next,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs a program on a CRT, which draws every cycle.
+fn run_on_crt(
+    program: &[Instruction],
+    config: CrtConfig,
+    max_cycles: usize,
+) -> Result<Crt, CycleLimitExceeded> {
+    let mut crt = Crt::new(config);
+    Cpu::with_screen(config).run(program, max_cycles, &mut crt)?;
+    Ok(crt)
+}
+
+fn jump(pc: usize, offset: i32) -> Option<usize> {
+    pc.checked_add_signed(offset as isize)
 }
 
-fn solution_part_1() -> i32 {
+pub fn solution_part_1() -> i32 {
     let instructions = parse_input();
-    let mut cpu = Cpu::new();
-    for instruction in instructions {
-        cpu.execute(instruction);
-    }
-    [20usize, 60, 100, 140, 180, 220]
-        .iter()
-        .map(|&cycle| cpu.signal_strength(cycle))
-        .sum()
+    let mut signal = SignalStrength::new(Schedule::puzzle());
+    Cpu::new()
+        .run(&instructions, MAX_CYCLES, &mut signal)
+        .expect("The puzzle program halts");
+    i32::try_from(signal.total).expect("The signal strength fits in an i32")
 }
 
-fn solution_part_2() -> String {
+pub fn solution_part_2() -> String {
     let instructions = parse_input();
-    let mut cpu = Cpu::new();
-    for instruction in instructions {
-        cpu.execute(instruction);
+    run_on_crt(&instructions, CrtConfig::default(), MAX_CYCLES)
+        .expect("The puzzle program halts")
+        .display()
+}
+
+pub fn solution_part_2_letters() -> Result<String, OcrError> {
+    recognize(&solution_part_2(), OcrConfig::default())
+}
+
+// Runs a program on a CRT with the given geometry and returns what is displayed. Fails
+// when the program runs for more than `max_cycles`.
+pub fn render(source: &str, config: CrtConfig, max_cycles: usize) -> Result<String, String> {
+    let instructions = assemble(source).map_err(|e| e.to_string())?;
+    let crt = run_on_crt(&instructions, config, max_cycles).map_err(|e| e.to_string())?;
+    Ok(crt.display())
+}
+
+// Values of X at the given cycles and the sum of their signal strengths, running the
+// program as it is read.
+pub fn stream_signal(
+    reader: impl io::BufRead,
+    cycles: &[usize],
+) -> Result<(Vec<i32>, i64), String> {
+    stream::signal_strength(reader, cycles).map_err(|e| e.to_string())
+}
+
+// A program of `addx` and `noop` drawing `text` on the default CRT.
+pub fn synthesize(text: &str, seed: u64) -> Result<String, String> {
+    synthesizer::synthesize(text, seed).map_err(|e| e.to_string())
+}
+
+#[derive(Clone)]
+pub struct CpuSimulation {
+    cpu: Cpu,
+    crt: Crt,
+    instructions: Vec<Instruction>,
+    next: usize,
+}
+
+impl CpuSimulation {
+    fn new(instructions: Vec<Instruction>) -> Self {
+        Self {
+            cpu: Cpu::new(),
+            crt: Crt::new(CrtConfig::default()),
+            instructions,
+            next: 0,
+        }
+    }
+}
+
+impl Simulation for CpuSimulation {
+    // A step executes a whole instruction.
+    fn step(&mut self) -> bool {
+        let Some(&instruction) = self.instructions.get(self.next) else {
+            return false;
+        };
+        let offset = self.cpu.execute(instruction, &mut self.crt);
+        self.next = jump(self.next, offset).unwrap_or(self.instructions.len());
+        true
+    }
+
+    fn frame(&self) -> String {
+        format!(
+            "cycle {} X={}\n{}",
+            self.cpu.cycles,
+            self.cpu.x(),
+            self.crt.display()
+        )
     }
-    cpu.crt.display()
+
+    fn inspect(&self) -> String {
+        let next = self
+            .instructions
+            .get(self.next)
+            .map_or("none".to_owned(), |instruction| instruction.to_string());
+        let registers: Vec<_> = Register::ALL
+            .iter()
+            .map(|&register| format!("{} {}", register, self.cpu.get(register)))
+            .collect();
+        let pixel = self.cpu.next_pixel().map_or("none".to_owned(), |pixel| {
+            format!("row {} col {}", pixel.row, pixel.col)
+        });
+        format!(
+            "cycle {}\n{}\nsprite center {}\nnext CRT pixel {}\nnext instruction #{}: {}",
+            self.cpu.cycles,
+            registers.join("\n"),
+            self.cpu.x(),
+            pixel,
+            self.next,
+            next
+        )
+    }
+}
+
+pub fn simulation() -> CpuSimulation {
+    CpuSimulation::new(parse_input())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::problem_10::*;
+    use crate::problem_10::{isa::Operand, *};
 
     #[test]
     fn problem_10_solution_part_1_test() {
@@ -150,4 +402,96 @@ mod tests {
     fn problem_10_solution_part_2_test() {
         println!("problem 10 solution 2: \n{}", solution_part_2());
     }
+
+    #[test]
+    fn problem_10_solution_part_2_letters_test() {
+        assert_eq!(solution_part_2_letters(), Ok("FJUBULRZ".to_owned()));
+    }
+
+    #[test]
+    fn problem_10_simulation_test() {
+        let mut simulation = CpuSimulation::new(vec![
+            Instruction::Noop,
+            Instruction::Addx(Operand::Immediate(3)),
+        ]);
+        while simulation.step() {}
+        let frame = simulation.frame();
+        assert!(frame.starts_with("cycle 3 X=4\n### "));
+    }
+
+    #[test]
+    fn problem_10_crt_overflow_test() {
+        let source = "mov x 0\nnoop\nnoop\nmov x 2\nnoop\nnoop\nmov x 1\nnoop\nnoop";
+        let config = |overflow| CrtConfig::new(3, 2, 1, overflow).unwrap();
+        assert_eq!(
+            render(source, config("stop"), MAX_CYCLES).unwrap(),
+            "   \n# #\n"
+        );
+        assert_eq!(
+            render(source, config("wrap"), MAX_CYCLES).unwrap(),
+            " # \n# #\n"
+        );
+        assert_eq!(
+            render(source, config("scroll"), MAX_CYCLES).unwrap(),
+            "# #\n # \n"
+        );
+        assert!(CrtConfig::new(0, 6, 3, "stop").is_err());
+    }
+
+    #[test]
+    fn problem_10_full_screen_pixel_test() {
+        let next_pixel = |overflow| {
+            let mut cpu = Cpu::with_screen(CrtConfig::new(1, 2, 3, overflow).unwrap());
+            cpu.cycle(2, &mut ());
+            cpu.next_pixel().map(|pixel| (pixel.row, pixel.col))
+        };
+        assert_eq!(next_pixel("stop"), None);
+        assert_eq!(next_pixel("wrap"), Some((0, 0)));
+        assert_eq!(next_pixel("scroll"), Some((1, 0)));
+    }
+
+    #[test]
+    fn problem_10_sprite_width_test() {
+        let visible_columns = |sprite_width| {
+            let config = CrtConfig::new(20, 1, sprite_width, "stop").unwrap();
+            (0..20)
+                .filter(|&col| config.is_lit(col, 10))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible_columns(5), vec![8, 9, 10, 11, 12]);
+        assert_eq!(visible_columns(2), vec![10, 11]);
+        assert!(visible_columns(0).is_empty());
+    }
+
+    #[test]
+    fn problem_10_run_test() {
+        // Multiplies X by 3 three times, using Y as counter
+        let program = assemble(
+            "      mov y 3
+             loop: mov z x
+                   mov x y
+                   subx 1
+                   mov y x
+                   mov x z
+                   mulx 3
+                   jnz y loop",
+        )
+        .unwrap();
+        let mut cpu = Cpu::new();
+        cpu.run(&program, MAX_CYCLES, &mut ()).unwrap();
+        assert_eq!(cpu.x(), 27);
+        assert_eq!(cpu.get(Register::Y), 0);
+        assert_eq!(cpu.cycles, 1 + 3 * 11);
+        let forever = assemble("addx 1\nloop: jmp loop").unwrap();
+        let mut cpu = Cpu::new();
+        assert_eq!(
+            cpu.run(&forever, 100, &mut ()),
+            Err(CycleLimitExceeded { limit: 100 })
+        );
+        assert_eq!(cpu.cycles, 100);
+        assert_eq!(
+            render("jmp 0", CrtConfig::default(), 10).unwrap_err(),
+            "the program did not halt within 10 cycles"
+        );
+    }
 }
@@ -0,0 +1,158 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use super::isa::{
+    is_label, parse_instruction_with_labels, Instruction, Operand, OperandKind, ParseError,
+    ParseErrorKind,
+};
+
+const COMMENT_MARKERS: [char; 2] = [';', '#'];
+
+// Splits a line into its labels and its instruction text, which is empty when the
+// line has no instruction.
+pub fn split_line(ln: &str) -> Result<(Vec<&str>, &str), ParseErrorKind> {
+    let mut text = ln.split(COMMENT_MARKERS).next().unwrap().trim();
+    let mut labels = vec![];
+    while let Some((label, rest)) = text.split_once(':') {
+        let label = label.trim();
+        if !is_label(label) {
+            return Err(ParseErrorKind::InvalidLabel(label.to_owned()));
+        }
+        labels.push(label);
+        text = rest.trim();
+    }
+    Ok((labels, text))
+}
+
+// A line holds any number of `label:` prefixes, then an optional instruction and an
+// optional comment. Labels name the index of the next instruction.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, ParseError> {
+    let mut labels = HashMap::new();
+    let mut instructions = vec![];
+    for (i, ln) in source.lines().enumerate() {
+        let error = |kind| ParseError { line: i + 1, kind };
+        let (line_labels, text) = split_line(ln).map_err(error)?;
+        for label in line_labels {
+            if labels.insert(label, instructions.len()).is_some() {
+                return Err(error(ParseErrorKind::DuplicateLabel(label.to_owned())));
+            }
+        }
+        if !text.is_empty() {
+            instructions.push((i + 1, text));
+        }
+    }
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(index, &(line, text))| {
+            parse_instruction_with_labels(text, |label| {
+                labels
+                    .get(label)
+                    .map(|&target| target as i32 - index as i32)
+            })
+            .map_err(|kind| ParseError { line, kind })
+        })
+        .collect()
+}
+
+fn target_operands(instruction: &Instruction) -> impl Iterator<Item = (OperandKind, Operand)> {
+    instruction
+        .spec()
+        .operands
+        .iter()
+        .copied()
+        .zip(instruction.operands())
+}
+
+// Targets past the last instruction are kept, since jumping there ends the program.
+fn jump_target(index: usize, instruction: &Instruction, len: usize) -> Option<usize> {
+    target_operands(instruction).find_map(|operand| match operand {
+        (OperandKind::Target, Operand::Immediate(offset)) => index
+            .checked_add_signed(offset as isize)
+            .filter(|&target| target <= len),
+        _ => None,
+    })
+}
+
+// Canonical text: one instruction per line, and jump targets inside the program
+// replaced by labels numbered in program order.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let targets: BTreeSet<_> = program
+        .iter()
+        .enumerate()
+        .filter_map(|(i, instruction)| jump_target(i, instruction, program.len()))
+        .collect();
+    let labels: HashMap<_, _> = targets
+        .into_iter()
+        .enumerate()
+        .map(|(n, target)| (target, format!("L{}", n)))
+        .collect();
+    let mut out = String::new();
+    for (i, instruction) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&i) {
+            writeln!(out, "{}:", label).unwrap();
+        }
+        out += instruction.spec().mnemonic;
+        let target = jump_target(i, instruction, program.len());
+        for (kind, operand) in target_operands(instruction) {
+            match (kind, target.and_then(|t| labels.get(&t))) {
+                (OperandKind::Target, Some(label)) => write!(out, " {}", label).unwrap(),
+                _ => write!(out, " {}", operand).unwrap(),
+            }
+        }
+        out.push('\n');
+    }
+    if let Some(label) = labels.get(&program.len()) {
+        writeln!(out, "{}:", label).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_10::{assembler::*, isa::Register};
+
+    const COUNTDOWN: &str = "\
+; counts down from 3
+        mov y 3
+loop:   mov x y   # X follows Y
+        subx 1
+
+        mov y x
+        jnz y loop
+end: done:
+";
+
+    #[test]
+    fn assembler_assemble_test() {
+        let program = assemble(COUNTDOWN).unwrap();
+        assert_eq!(program.len(), 5);
+        assert_eq!(
+            program[4],
+            Instruction::Jnz(Register::Y, Operand::Immediate(-3))
+        );
+        let error = assemble("noop\naddx 2\n\nadd 4\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: unknown mnemonic `add`");
+        let error = assemble("a: noop\na: noop\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: label `a` already defined");
+        let error = assemble("noop\njmp nowhere\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: undefined label `nowhere`");
+        let error = assemble("x: noop\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: invalid label `x`");
+    }
+
+    #[test]
+    fn assembler_disassemble_test() {
+        let program = assemble(COUNTDOWN).unwrap();
+        let text = disassemble(&program);
+        assert_eq!(text, "mov y 3\nL0:\nmov x y\nsubx 1\nmov y x\njnz y L0\n");
+        assert_eq!(assemble(&text).unwrap(), program);
+
+        let program = assemble("jmp 2\njz x -5\nnoop\njmp y\n").unwrap();
+        let text = disassemble(&program);
+        assert_eq!(text, "jmp L0\njz x -5\nL0:\nnoop\njmp y\n");
+        assert_eq!(assemble(&text).unwrap(), program);
+    }
+}
@@ -0,0 +1,453 @@
+use std::{array, fmt, iter, ops::Deref, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+pub const REGISTERS: usize = 4;
+
+impl Register {
+    pub const ALL: [Register; REGISTERS] = [Self::X, Self::Y, Self::Z, Self::W];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Z => "z",
+            Self::W => "w",
+        }
+    }
+}
+
+impl FromStr for Register {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|register| register.name() == s)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Immediate(i32),
+}
+
+impl FromStr for Operand {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Self::Register)
+            .or_else(|_| s.parse().map(Self::Immediate).map_err(|_| ()))
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(register) => write!(f, "{}", register),
+            Self::Immediate(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperandKind {
+    Register,
+    // A register or an immediate
+    Value,
+    // A value giving a jump offset, which can also be written as a label
+    Target,
+}
+
+// Arithmetic instructions always target X, the register driving the CRT sprite.
+// Jump offsets are relative to the jump itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Addx(Operand),
+    Subx(Operand),
+    Mulx(Operand),
+    Mov(Register, Operand),
+    Jmp(Operand),
+    Jz(Register, Operand),
+    Jnz(Register, Operand),
+}
+
+pub type Registers = [i32; REGISTERS];
+
+pub const MAX_OPERANDS: usize = 2;
+
+// The operands of an instruction, kept inline so that executing one does not allocate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Operands {
+    operands: [Operand; MAX_OPERANDS],
+    len: usize,
+}
+
+impl Operands {
+    fn new(given: &[Operand]) -> Self {
+        let mut operands = [Operand::Immediate(0); MAX_OPERANDS];
+        operands[..given.len()].copy_from_slice(given);
+        Self {
+            operands,
+            len: given.len(),
+        }
+    }
+}
+
+impl Deref for Operands {
+    type Target = [Operand];
+
+    fn deref(&self) -> &[Operand] {
+        &self.operands[..self.len]
+    }
+}
+
+impl IntoIterator for Operands {
+    type Item = Operand;
+    type IntoIter = iter::Take<array::IntoIter<Operand, MAX_OPERANDS>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operands.into_iter().take(self.len)
+    }
+}
+
+pub struct InstructionSpec {
+    pub mnemonic: &'static str,
+    pub operands: &'static [OperandKind],
+    pub cycles: u32,
+    // Only called with operands matching `operands`
+    build: fn(&[Operand]) -> Instruction,
+    // Applies the effects once the cycles have elapsed and returns the offset to add
+    // to the program counter. Only called with operands matching `operands`.
+    execute: fn(&mut Registers, &[Operand]) -> i32,
+}
+
+fn register(operand: Operand) -> Register {
+    match operand {
+        Operand::Register(register) => register,
+        Operand::Immediate(_) => unreachable!("Operand kinds are checked before building"),
+    }
+}
+
+pub fn value(registers: &Registers, operand: Operand) -> i32 {
+    match operand {
+        Operand::Register(register) => registers[register.index()],
+        Operand::Immediate(n) => n,
+    }
+}
+
+// Sets X to `op` of X and the value of `operand`, then moves to the next instruction.
+fn update_x(registers: &mut Registers, operand: Operand, op: fn(i32, i32) -> i32) -> i32 {
+    let x = Register::X.index();
+    registers[x] = op(registers[x], value(registers, operand));
+    1
+}
+
+// Jumps by the value of `target` when `condition` holds for the value of `register`.
+fn jump_if(registers: &Registers, ops: &[Operand], condition: fn(i32) -> bool) -> i32 {
+    if condition(registers[register(ops[0]).index()]) {
+        value(registers, ops[1])
+    } else {
+        1
+    }
+}
+
+pub static NOOP: InstructionSpec = InstructionSpec {
+    mnemonic: "noop",
+    operands: &[],
+    cycles: 1,
+    build: |_| Instruction::Noop,
+    execute: |_, _| 1,
+};
+
+pub static ADDX: InstructionSpec = InstructionSpec {
+    mnemonic: "addx",
+    operands: &[OperandKind::Value],
+    cycles: 2,
+    build: |ops| Instruction::Addx(ops[0]),
+    execute: |registers, ops| update_x(registers, ops[0], i32::wrapping_add),
+};
+
+pub static SUBX: InstructionSpec = InstructionSpec {
+    mnemonic: "subx",
+    operands: &[OperandKind::Value],
+    cycles: 2,
+    build: |ops| Instruction::Subx(ops[0]),
+    execute: |registers, ops| update_x(registers, ops[0], i32::wrapping_sub),
+};
+
+pub static MULX: InstructionSpec = InstructionSpec {
+    mnemonic: "mulx",
+    operands: &[OperandKind::Value],
+    cycles: 3,
+    build: |ops| Instruction::Mulx(ops[0]),
+    execute: |registers, ops| update_x(registers, ops[0], i32::wrapping_mul),
+};
+
+pub static MOV: InstructionSpec = InstructionSpec {
+    mnemonic: "mov",
+    operands: &[OperandKind::Register, OperandKind::Value],
+    cycles: 1,
+    build: |ops| Instruction::Mov(register(ops[0]), ops[1]),
+    execute: |registers, ops| {
+        registers[register(ops[0]).index()] = value(registers, ops[1]);
+        1
+    },
+};
+
+pub static JMP: InstructionSpec = InstructionSpec {
+    mnemonic: "jmp",
+    operands: &[OperandKind::Target],
+    cycles: 1,
+    build: |ops| Instruction::Jmp(ops[0]),
+    execute: |registers, ops| value(registers, ops[0]),
+};
+
+pub static JZ: InstructionSpec = InstructionSpec {
+    mnemonic: "jz",
+    operands: &[OperandKind::Register, OperandKind::Target],
+    cycles: 2,
+    build: |ops| Instruction::Jz(register(ops[0]), ops[1]),
+    execute: |registers, ops| jump_if(registers, ops, |v| v == 0),
+};
+
+pub static JNZ: InstructionSpec = InstructionSpec {
+    mnemonic: "jnz",
+    operands: &[OperandKind::Register, OperandKind::Target],
+    cycles: 2,
+    build: |ops| Instruction::Jnz(register(ops[0]), ops[1]),
+    execute: |registers, ops| jump_if(registers, ops, |v| v != 0),
+};
+
+// Every instruction, for looking them up by mnemonic
+pub static INSTRUCTION_SET: [&InstructionSpec; 8] =
+    [&NOOP, &ADDX, &SUBX, &MULX, &MOV, &JMP, &JZ, &JNZ];
+
+impl Instruction {
+    pub fn spec(&self) -> &'static InstructionSpec {
+        match self {
+            Self::Noop => &NOOP,
+            Self::Addx(_) => &ADDX,
+            Self::Subx(_) => &SUBX,
+            Self::Mulx(_) => &MULX,
+            Self::Mov(..) => &MOV,
+            Self::Jmp(_) => &JMP,
+            Self::Jz(..) => &JZ,
+            Self::Jnz(..) => &JNZ,
+        }
+    }
+
+    // Applies the effects of the instruction once its cycles have elapsed. Returns the
+    // offset to add to the program counter.
+    pub fn execute(&self, registers: &mut Registers) -> i32 {
+        (self.spec().execute)(registers, &self.operands())
+    }
+
+    pub fn cycles(&self) -> u32 {
+        self.spec().cycles
+    }
+
+    pub fn operands(&self) -> Operands {
+        match *self {
+            Self::Noop => Operands::new(&[]),
+            Self::Addx(v) | Self::Subx(v) | Self::Mulx(v) | Self::Jmp(v) => Operands::new(&[v]),
+            Self::Mov(r, v) | Self::Jz(r, v) | Self::Jnz(r, v) => {
+                Operands::new(&[Operand::Register(r), v])
+            }
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec().mnemonic)?;
+        for operand in self.operands() {
+            write!(f, " {}", operand)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingMnemonic,
+    UnknownMnemonic(String),
+    OperandCount {
+        mnemonic: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    InvalidOperand {
+        mnemonic: &'static str,
+        operand: String,
+    },
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingMnemonic => write!(f, "missing mnemonic"),
+            Self::UnknownMnemonic(mnemonic) => write!(f, "unknown mnemonic `{}`", mnemonic),
+            Self::OperandCount {
+                mnemonic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} operand(s), found {}",
+                mnemonic, expected, found
+            ),
+            Self::InvalidOperand { mnemonic, operand } => {
+                write!(f, "invalid operand `{}` for `{}`", operand, mnemonic)
+            }
+            Self::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            Self::DuplicateLabel(label) => write!(f, "label `{}` already defined", label),
+            Self::UndefinedLabel(label) => write!(f, "undefined label `{}`", label),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+// Labels are identifiers that cannot be confused with registers.
+pub fn is_label(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && word.parse::<Register>().is_err()
+}
+
+pub fn parse_instruction(text: &str) -> Result<Instruction, ParseErrorKind> {
+    parse_instruction_with_labels(text, |_| None)
+}
+
+// `label_offset` gives the jump offset of a label used as a target operand.
+pub fn parse_instruction_with_labels(
+    text: &str,
+    label_offset: impl Fn(&str) -> Option<i32>,
+) -> Result<Instruction, ParseErrorKind> {
+    let mut words = text.split_whitespace();
+    let mnemonic = words.next().ok_or(ParseErrorKind::MissingMnemonic)?;
+    let spec = INSTRUCTION_SET
+        .into_iter()
+        .find(|spec| spec.mnemonic == mnemonic)
+        .ok_or_else(|| ParseErrorKind::UnknownMnemonic(mnemonic.to_owned()))?;
+    let words: Vec<_> = words.collect();
+    if words.len() != spec.operands.len() {
+        return Err(ParseErrorKind::OperandCount {
+            mnemonic: spec.mnemonic,
+            expected: spec.operands.len(),
+            found: words.len(),
+        });
+    }
+    let operands = words
+        .iter()
+        .zip(spec.operands)
+        .map(|(word, kind)| match (word.parse(), kind) {
+            (Ok(operand @ Operand::Register(_)), _)
+            | (Ok(operand @ Operand::Immediate(_)), OperandKind::Value | OperandKind::Target) => {
+                Ok(operand)
+            }
+            (Err(_), OperandKind::Target) if is_label(word) => label_offset(word)
+                .map(Operand::Immediate)
+                .ok_or_else(|| ParseErrorKind::UndefinedLabel(word.to_string())),
+            _ => Err(ParseErrorKind::InvalidOperand {
+                mnemonic: spec.mnemonic,
+                operand: word.to_string(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((spec.build)(&operands))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_10::isa::*;
+
+    #[test]
+    fn isa_execute_test() {
+        let mut registers = [5, 0, 2, 0];
+        let run = |text: &str, registers: &mut Registers| {
+            parse_instruction(text).unwrap().execute(registers)
+        };
+        assert_eq!(run("mulx z", &mut registers), 1);
+        assert_eq!(run("subx -1", &mut registers), 1);
+        assert_eq!(run("mov w x", &mut registers), 1);
+        assert_eq!(registers, [11, 0, 2, 11]);
+        assert_eq!(run("jz y -4", &mut registers), -4);
+        assert_eq!(run("jnz y -4", &mut registers), 1);
+        assert_eq!(run("jmp z", &mut registers), 2);
+        let mov = parse_instruction("mov w 7").unwrap();
+        assert_eq!(
+            *mov.operands(),
+            [Operand::Register(Register::W), Operand::Immediate(7)]
+        );
+        assert!(parse_instruction("noop").unwrap().operands().is_empty());
+    }
+
+    #[test]
+    fn isa_parse_instruction_test() {
+        for text in ["noop", "addx -3", "mulx y", "mov w 7", "jnz z -2"] {
+            assert_eq!(parse_instruction(text).unwrap().to_string(), text);
+        }
+        assert_eq!(
+            parse_instruction("mov 3 x"),
+            Err(ParseErrorKind::InvalidOperand {
+                mnemonic: "mov",
+                operand: "3".to_owned()
+            })
+        );
+        assert_eq!(parse_instruction("subx 1").unwrap().cycles(), 2);
+        // Every spec builds instructions that map back to it
+        for spec in INSTRUCTION_SET {
+            let operands: Vec<_> = spec
+                .operands
+                .iter()
+                .map(|kind| match kind {
+                    OperandKind::Register => Operand::Register(Register::Y),
+                    OperandKind::Value | OperandKind::Target => Operand::Immediate(1),
+                })
+                .collect();
+            assert!(std::ptr::eq((spec.build)(&operands).spec(), spec));
+        }
+        assert_eq!(
+            parse_instruction("jnz y end"),
+            Err(ParseErrorKind::UndefinedLabel("end".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use super::{
+    isa::{Instruction, Register, REGISTERS},
+    jump, Cpu, Pixel,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(self, lhs: i32, rhs: i32) -> bool {
+        match self {
+            Self::Less => lhs < rhs,
+            Self::LessOrEqual => lhs <= rhs,
+            Self::Equal => lhs == rhs,
+            Self::NotEqual => lhs != rhs,
+            Self::GreaterOrEqual => lhs >= rhs,
+            Self::Greater => lhs > rhs,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "<" => Ok(Self::Less),
+            "<=" => Ok(Self::LessOrEqual),
+            "==" => Ok(Self::Equal),
+            "!=" => Ok(Self::NotEqual),
+            ">=" => Ok(Self::GreaterOrEqual),
+            ">" => Ok(Self::Greater),
+            _ => Err(()),
+        }
+    }
+}
+
+// Breakpoints are checked between cycles. A cycle breakpoint stops right before the
+// given cycle runs, an instruction breakpoint before the instruction at that index
+// starts, and a register breakpoint when its condition goes from false to true.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    Cycle(usize),
+    Instruction(usize),
+    Register(Register, Comparison, i32),
+}
+
+// Written as `cycle 20`, `instruction 3` or `X < 0`.
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let error = || format!("Invalid breakpoint {}", s);
+        match words[..] {
+            ["cycle", n] => n.parse().map(Self::Cycle).map_err(|_| error()),
+            ["instruction", n] => n.parse().map(Self::Instruction).map_err(|_| error()),
+            [register, comparison, value] => Ok(Self::Register(
+                register.to_lowercase().parse().map_err(|_| error())?,
+                comparison.parse().map_err(|_| error())?,
+                value.parse().map_err(|_| error())?,
+            )),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    // Index of the breakpoint that was hit
+    Breakpoint(usize),
+    Halted,
+}
+
+// State during a cycle: the registers, the instruction being executed and the pixel
+// being drawn, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleRecord {
+    pub cycle: usize,
+    pub pc: usize,
+    pub registers: [i32; REGISTERS],
+    pub pixel: Option<Pixel>,
+}
+
+impl fmt::Display for CycleRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cycle {:>4} pc {:>4}", self.cycle, self.pc)?;
+        for register in Register::ALL {
+            write!(f, " {}={:<5}", register, self.registers[register.index()])?;
+        }
+        match self.pixel {
+            Some(Pixel { row, col, lit }) => write!(
+                f,
+                " pixel ({}, {}) {}",
+                row,
+                col,
+                if lit { '#' } else { '.' }
+            ),
+            None => write!(f, " pixel none"),
+        }
+    }
+}
+
+pub struct Monitor {
+    cpu: Cpu,
+    program: Vec<Instruction>,
+    pc: usize,
+    // Cycles already spent on the instruction at `pc`
+    elapsed: u32,
+    breakpoints: Vec<Breakpoint>,
+    history: Vec<CycleRecord>,
+}
+
+impl Monitor {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            cpu: Cpu::new(),
+            program,
+            pc: 0,
+            elapsed: 0,
+            breakpoints: vec![],
+            history: vec![],
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn register(&self, register: Register) -> i32 {
+        self.cpu.get(register)
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    // Number of cycles run so far
+    pub fn cycle(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    pub fn step_cycle(&mut self) -> Option<CycleRecord> {
+        let instruction = *self.program.get(self.pc)?;
+        let record = CycleRecord {
+            cycle: self.history.len() + 1,
+            pc: self.pc,
+            registers: self.cpu.registers,
+            pixel: self.cpu.next_pixel(),
+        };
+        self.cpu.cycle(1, &mut ());
+        self.elapsed += 1;
+        if self.elapsed == instruction.cycles() {
+            self.elapsed = 0;
+            let offset = self.cpu.apply(instruction);
+            self.pc = jump(self.pc, offset).unwrap_or(self.program.len());
+        }
+        self.history.push(record);
+        Some(record)
+    }
+
+    // Runs the remaining cycles of the current instruction.
+    pub fn step_instruction(&mut self) -> Vec<CycleRecord> {
+        let mut records = vec![];
+        while let Some(record) = self.step_cycle() {
+            records.push(record);
+            if self.elapsed == 0 {
+                break;
+            }
+        }
+        records
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        let next_cycle = self.history.len() + 1;
+        let previous_registers = self.history.last().map(|record| record.registers);
+        self.breakpoints
+            .iter()
+            .position(|&breakpoint| match breakpoint {
+                Breakpoint::Cycle(cycle) => cycle == next_cycle,
+                Breakpoint::Instruction(index) => self.elapsed == 0 && index == self.pc,
+                Breakpoint::Register(register, comparison, value) => {
+                    let holds = |registers: [i32; REGISTERS]| {
+                        comparison.holds(registers[register.index()], value)
+                    };
+                    holds(self.cpu.registers) && !previous_registers.is_some_and(holds)
+                }
+            })
+    }
+
+    // Runs at least one cycle, then until a breakpoint is hit or the program ends.
+    pub fn resume(&mut self) -> StopReason {
+        if self.step_cycle().is_none() {
+            return StopReason::Halted;
+        }
+        loop {
+            if let Some(index) = self.hit_breakpoint() {
+                return StopReason::Breakpoint(index);
+            }
+            if self.step_cycle().is_none() {
+                return StopReason::Halted;
+            }
+        }
+    }
+
+    // One line per cycle in `cycles`, skipping the ones that have not run yet.
+    pub fn dump_history(&self, cycles: RangeInclusive<usize>) -> String {
+        let first = cycles.start().max(&1) - 1;
+        let last = (*cycles.end()).min(self.history.len());
+        self.history
+            .get(first..last)
+            .unwrap_or_default()
+            .iter()
+            .map(|record| record.to_string() + "\n")
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_10::{assembler::assemble, monitor::*},
+        utilities::read_file,
+    };
+
+    fn sample_monitor() -> Monitor {
+        Monitor::new(assemble(&read_file("problem_10_sample")).unwrap())
+    }
+
+    #[test]
+    fn monitor_breakpoints_test() {
+        let mut monitor = sample_monitor();
+        let during_20 = monitor.add_breakpoint("cycle 20".parse().unwrap());
+        let third = monitor.add_breakpoint("instruction 3".parse().unwrap());
+        assert_eq!(monitor.resume(), StopReason::Breakpoint(third));
+        assert_eq!(monitor.cycle(), 6);
+        assert_eq!(monitor.resume(), StopReason::Breakpoint(during_20));
+        // The puzzle text says X is 21 during the 20th cycle
+        assert_eq!(monitor.step_cycle().unwrap().registers[0], 21);
+        monitor.clear_breakpoints();
+        assert_eq!(monitor.resume(), StopReason::Halted);
+        assert_eq!(monitor.cycle(), 240);
+    }
+
+    #[test]
+    fn monitor_register_watch_test() {
+        let program = assemble("addx -3\nnoop\naddx 5\naddx -4\nnoop").unwrap();
+        let mut monitor = Monitor::new(program);
+        let negative = monitor.add_breakpoint("X < 0".parse().unwrap());
+        assert_eq!(monitor.resume(), StopReason::Breakpoint(negative));
+        assert_eq!((monitor.cycle(), monitor.register(Register::X)), (2, -2));
+        // Stays negative during the noop, so the watch does not trigger again
+        assert_eq!(monitor.resume(), StopReason::Breakpoint(negative));
+        assert_eq!((monitor.cycle(), monitor.register(Register::X)), (7, -1));
+    }
+
+    #[test]
+    fn monitor_stepping_test() {
+        let mut monitor = sample_monitor();
+        assert_eq!(monitor.step_instruction().len(), 2);
+        assert_eq!(monitor.step_cycle().unwrap().pc, 1);
+        assert_eq!(monitor.step_instruction().len(), 1);
+        assert_eq!(monitor.pc(), 2);
+        assert_eq!(
+            monitor.dump_history(2..=3),
+            "cycle    2 pc    0 x=1     y=0     z=0     w=0     pixel (0, 1) #\n\
+             cycle    3 pc    1 x=16    y=0     z=0     w=0     pixel (0, 2) .\n"
+        );
+    }
+}
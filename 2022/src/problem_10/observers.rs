@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+
+use super::Pixel;
+
+// Called during every cycle, with the cycle number (1 based), the value of X during
+// the cycle and the pixel the CRT draws, if any.
+pub trait CycleObserver {
+    fn observe(&mut self, cycle: usize, x: i32, pixel: Option<Pixel>);
+}
+
+impl CycleObserver for () {
+    fn observe(&mut self, _cycle: usize, _x: i32, _pixel: Option<Pixel>) {}
+}
+
+impl<T: CycleObserver + ?Sized> CycleObserver for &mut T {
+    fn observe(&mut self, cycle: usize, x: i32, pixel: Option<Pixel>) {
+        (**self).observe(cycle, x, pixel);
+    }
+}
+
+// Both observers see every cycle, the first one first.
+impl<A: CycleObserver, B: CycleObserver> CycleObserver for (A, B) {
+    fn observe(&mut self, cycle: usize, x: i32, pixel: Option<Pixel>) {
+        self.0.observe(cycle, x, pixel);
+        self.1.observe(cycle, x, pixel);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    At(BTreeSet<usize>),
+    Every { first: usize, step: usize },
+}
+
+impl Schedule {
+    // The cycles sampled by the puzzle
+    pub fn puzzle() -> Self {
+        Self::At(BTreeSet::from([20, 60, 100, 140, 180, 220]))
+    }
+
+    pub fn contains(&self, cycle: usize) -> bool {
+        match self {
+            Self::At(cycles) => cycles.contains(&cycle),
+            Self::Every { first, step: 0 } => cycle == *first,
+            Self::Every { first, step } => cycle >= *first && (cycle - first).is_multiple_of(*step),
+        }
+    }
+}
+
+// Sum of cycle number times X over the scheduled cycles.
+pub struct SignalStrength {
+    schedule: Schedule,
+    pub total: i64,
+}
+
+impl SignalStrength {
+    pub fn new(schedule: Schedule) -> Self {
+        Self { schedule, total: 0 }
+    }
+}
+
+impl CycleObserver for SignalStrength {
+    fn observe(&mut self, cycle: usize, x: i32, _pixel: Option<Pixel>) {
+        if self.schedule.contains(cycle) {
+            self.total += cycle as i64 * x as i64;
+        }
+    }
+}
+
+// Value of X at the scheduled cycles.
+pub struct Sampler {
+    schedule: Schedule,
+    pub samples: Vec<(usize, i32)>,
+}
+
+impl Sampler {
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            samples: vec![],
+        }
+    }
+}
+
+impl CycleObserver for Sampler {
+    fn observe(&mut self, cycle: usize, x: i32, _pixel: Option<Pixel>) {
+        if self.schedule.contains(cycle) {
+            self.samples.push((cycle, x));
+        }
+    }
+}
+
+// Statistics of X over all cycles, and how many pixels were lit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegisterStats {
+    pub cycles: usize,
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    pub sum: i64,
+    pub lit_pixels: usize,
+}
+
+impl RegisterStats {
+    pub fn mean(&self) -> Option<f64> {
+        (self.cycles > 0).then(|| self.sum as f64 / self.cycles as f64)
+    }
+}
+
+impl CycleObserver for RegisterStats {
+    fn observe(&mut self, _cycle: usize, x: i32, pixel: Option<Pixel>) {
+        self.cycles += 1;
+        self.min = Some(self.min.map_or(x, |min| min.min(x)));
+        self.max = Some(self.max.map_or(x, |max| max.max(x)));
+        self.sum += x as i64;
+        self.lit_pixels += pixel.is_some_and(|pixel| pixel.lit) as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_10::{assembler::assemble, observers::*, Cpu, Crt, CrtConfig, MAX_CYCLES},
+        utilities::read_file,
+    };
+
+    #[test]
+    fn observers_signal_strength_test() {
+        let program = assemble(&read_file("problem_10_sample")).unwrap();
+        let mut signal = SignalStrength::new(Schedule::puzzle());
+        let mut sampler = Sampler::new(Schedule::Every {
+            first: 20,
+            step: 40,
+        });
+        Cpu::new()
+            .run(&program, MAX_CYCLES, &mut (&mut signal, &mut sampler))
+            .unwrap();
+        assert_eq!(signal.total, 13140);
+        assert_eq!(
+            sampler.samples,
+            vec![
+                (20, 21),
+                (60, 19),
+                (100, 18),
+                (140, 21),
+                (180, 16),
+                (220, 18)
+            ]
+        );
+    }
+
+    #[test]
+    fn observers_stats_and_crt_test() {
+        let program = assemble("noop\naddx 3\naddx -5\nnoop").unwrap();
+        let config = CrtConfig::new(6, 1, 3, "stop").unwrap();
+        let mut stats = RegisterStats::default();
+        let mut crt = Crt::new(config);
+        Cpu::with_screen(config)
+            .run(&program, MAX_CYCLES, &mut (&mut stats, &mut crt))
+            .unwrap();
+        assert_eq!((stats.cycles, stats.min, stats.max), (6, Some(-1), Some(4)));
+        assert_eq!(stats.mean(), Some(10.0 / 6.0));
+        assert_eq!(stats.lit_pixels, 5);
+        assert_eq!(crt.display(), "##### \n");
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+// Glyphs are separated by one blank column
+pub const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+// The letters known to appear in puzzle outputs, `#` is a lit pixel.
+pub const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+pub fn glyph(letter: char) -> Option<&'static [&'static str; GLYPH_HEIGHT]> {
+    FONT.iter()
+        .find(|(c, _)| *c == letter)
+        .map(|(_, rows)| rows)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OcrConfig {
+    pub on: char,
+    pub off: char,
+}
+
+// Matches `Crt::display`
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self { on: '#', off: ' ' }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    WrongHeight(usize),
+    InvalidPixel { row: usize, col: usize, pixel: char },
+    // `index` is the position of the glyph in the text, `col` its first column
+    UnknownGlyphs(Vec<UnknownGlyph>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownGlyph {
+    pub index: usize,
+    pub col: usize,
+    pub rows: Vec<String>,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongHeight(height) => write!(
+                f,
+                "expected {} rows of pixels, found {}",
+                GLYPH_HEIGHT, height
+            ),
+            Self::InvalidPixel { row, col, pixel } => {
+                write!(f, "invalid pixel {:?} at row {} col {}", pixel, row, col)
+            }
+            Self::UnknownGlyphs(glyphs) => {
+                for glyph in glyphs {
+                    writeln!(f, "unknown glyph #{} at column {}:", glyph.index, glyph.col)?;
+                    for row in &glyph.rows {
+                        writeln!(f, "{}", row)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Blank cells are read as spaces, trailing ones are dropped.
+pub fn recognize(screen: &str, config: OcrConfig) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = screen
+        .lines()
+        .enumerate()
+        .map(|(row, ln)| {
+            ln.chars()
+                .enumerate()
+                .map(|(col, pixel)| match pixel {
+                    _ if pixel == config.on => Ok(true),
+                    _ if pixel == config.off => Ok(false),
+                    _ => Err(OcrError::InvalidPixel { row, col, pixel }),
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight(rows.len()));
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut text = String::new();
+    let mut unknown = vec![];
+    for (index, col) in (0..width).step_by(CELL_WIDTH).enumerate() {
+        let cell: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (col..col + GLYPH_WIDTH)
+                    .map(|c| if row.get(c) == Some(&true) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let letter = FONT
+            .iter()
+            .find(|(_, glyph)| glyph.iter().eq(cell.iter()))
+            .map(|(letter, _)| *letter);
+        match letter {
+            Some(letter) => text.push(letter),
+            None if cell.iter().all(|row| !row.contains('#')) => text.push(' '),
+            None => unknown.push(UnknownGlyph {
+                index,
+                col,
+                rows: cell,
+            }),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(OcrError::UnknownGlyphs(unknown));
+    }
+    Ok(text.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_10::ocr::*;
+
+    fn draw(text: &str) -> String {
+        (0..GLYPH_HEIGHT)
+            .map(|row| {
+                text.chars()
+                    .map(|c| glyph(c).map_or("....", |rows| rows[row]))
+                    .collect::<Vec<_>>()
+                    .join(".")
+                    + "\n"
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ocr_recognize_test() {
+        let config = OcrConfig { on: '#', off: '.' };
+        let letters: String = FONT.iter().map(|(letter, _)| letter).collect();
+        assert_eq!(recognize(&draw(&letters), config), Ok(letters));
+        assert_eq!(recognize(&draw("HI JO  "), config), Ok("HI JO".to_owned()));
+        let config = OcrConfig { on: 'X', off: '_' };
+        assert_eq!(
+            recognize(&draw("AB").replace('#', "X"), config),
+            Err(OcrError::InvalidPixel {
+                row: 0,
+                col: 0,
+                pixel: '.'
+            })
+        );
+    }
+
+    #[test]
+    fn ocr_unknown_glyph_test() {
+        let mut screen = draw("ABC");
+        // Turns the top left pixel of the C on
+        screen.replace_range(10..11, "#");
+        let error = recognize(&screen, OcrConfig { on: '#', off: '.' }).unwrap_err();
+        let OcrError::UnknownGlyphs(glyphs) = &error else {
+            panic!("Unexpected error {:?}", error);
+        };
+        assert_eq!((glyphs[0].index, glyphs[0].col), (2, 10));
+        assert!(error
+            .to_string()
+            .starts_with("unknown glyph #2 at column 10:\n###.\n"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::{fmt, io, io::BufRead};
+
+use super::{
+    assembler::split_line,
+    isa::{parse_instruction_with_labels, Instruction, OperandKind, ParseError, Register},
+    Cpu,
+};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+    // Jumps need the whole program, which is not kept
+    Jump { line: usize },
+    // Cycles are 1 based and must be asked in increasing order
+    InvalidCycle { cycle: usize },
+    Passed { cycle: usize, last: usize },
+    PastEnd { cycle: usize, cycles: usize },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read the program: {}", e),
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::Jump { line } => write!(f, "line {}: jumps cannot be streamed", line),
+            Self::InvalidCycle { cycle } => write!(f, "invalid cycle {}", cycle),
+            Self::Passed { cycle, last } => write!(
+                f,
+                "cycle {} comes before cycle {}, which was already asked for",
+                cycle, last
+            ),
+            Self::PastEnd { cycle, cycles } => write!(
+                f,
+                "cycle {} is past the end of the program, which runs for {} cycles",
+                cycle, cycles
+            ),
+        }
+    }
+}
+
+// Runs a program read one line at a time, keeping only the instruction being
+// executed.
+pub struct Stream<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    cpu: Cpu,
+    // The last cycle asked for
+    last: usize,
+    // The instruction that started after the ones already applied
+    current: Option<Instruction>,
+}
+
+impl<R: BufRead> Stream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            cpu: Cpu::new(),
+            last: 0,
+            current: None,
+        }
+    }
+
+    fn fetch(&mut self) -> Result<Option<Instruction>, StreamError> {
+        if self.current.is_some() {
+            return Ok(self.current);
+        }
+        for ln in self.lines.by_ref() {
+            self.line += 1;
+            let ln = ln.map_err(StreamError::Io)?;
+            let error = |kind| {
+                StreamError::Parse(ParseError {
+                    line: self.line,
+                    kind,
+                })
+            };
+            let (_, text) = split_line(&ln).map_err(error)?;
+            if text.is_empty() {
+                continue;
+            }
+            let instruction = parse_instruction_with_labels(text, |_| Some(0)).map_err(error)?;
+            if instruction.spec().operands.contains(&OperandKind::Target) {
+                return Err(StreamError::Jump { line: self.line });
+            }
+            self.current = Some(instruction);
+            return Ok(Some(instruction));
+        }
+        Ok(None)
+    }
+
+    // Value of `register` during `cycle`. Every call moves the program forward, so a
+    // cycle cannot be asked for after a later one.
+    pub fn register_at(&mut self, register: Register, cycle: usize) -> Result<i32, StreamError> {
+        if cycle == 0 {
+            return Err(StreamError::InvalidCycle { cycle });
+        }
+        if cycle < self.last {
+            return Err(StreamError::Passed {
+                cycle,
+                last: self.last,
+            });
+        }
+        self.last = cycle;
+        loop {
+            let Some(instruction) = self.fetch()? else {
+                return Err(StreamError::PastEnd {
+                    cycle,
+                    cycles: self.cpu.cycles,
+                });
+            };
+            if cycle <= self.cpu.cycles + instruction.cycles() as usize {
+                return Ok(self.cpu.get(register));
+            }
+            self.cpu.cycles += instruction.cycles() as usize;
+            self.cpu.apply(instruction);
+            self.current = None;
+        }
+    }
+
+    pub fn x_at(&mut self, cycle: usize) -> Result<i32, StreamError> {
+        self.register_at(Register::X, cycle)
+    }
+}
+
+// Values of X during `cycles`, which can come in any order, and the sum of cycle
+// number times X over them.
+pub fn signal_strength(
+    reader: impl BufRead,
+    cycles: &[usize],
+) -> Result<(Vec<i32>, i64), StreamError> {
+    let mut order: Vec<_> = (0..cycles.len()).collect();
+    order.sort_by_key(|&i| cycles[i]);
+    let mut stream = Stream::new(reader);
+    let mut values = vec![0; cycles.len()];
+    for i in order {
+        values[i] = stream.x_at(cycles[i])?;
+    }
+    let total = cycles
+        .iter()
+        .zip(&values)
+        .map(|(&cycle, &x)| cycle as i64 * x as i64)
+        .sum();
+    Ok((values, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use crate::{problem_10::stream::*, utilities::read_file};
+
+    // `count` repetitions of `addx 1` and `noop`, generated while being read
+    struct Generator {
+        count: usize,
+        pending: Vec<u8>,
+    }
+
+    impl Read for Generator {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() && self.count > 0 {
+                self.count -= 1;
+                self.pending = b"addx 1\nnoop\n".to_vec();
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn stream_signal_strength_test() {
+        let sample = read_file("problem_10_sample");
+        let cycles = [220, 20, 60, 100, 140, 180];
+        let (values, total) = signal_strength(sample.as_bytes(), &cycles).unwrap();
+        assert_eq!(total, 13140);
+        assert_eq!(values, vec![18, 21, 19, 18, 21, 16]);
+        let mut stream = Stream::new(sample.as_bytes());
+        assert_eq!(stream.x_at(20).unwrap(), 21);
+        assert_eq!(stream.x_at(20).unwrap(), 21);
+        assert!(matches!(
+            stream.x_at(19),
+            Err(StreamError::Passed {
+                cycle: 19,
+                last: 20
+            })
+        ));
+        assert!(matches!(
+            stream.x_at(241),
+            Err(StreamError::PastEnd {
+                cycle: 241,
+                cycles: 240
+            })
+        ));
+    }
+
+    #[test]
+    fn stream_errors_test() {
+        let mut stream = Stream::new("noop\nloop: jmp loop\n".as_bytes());
+        assert_eq!(stream.x_at(1).unwrap(), 1);
+        assert!(matches!(stream.x_at(2), Err(StreamError::Jump { line: 2 })));
+        let error = signal_strength("noop\naddx\n".as_bytes(), &[2]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: `addx` takes 1 operand(s), found 0"
+        );
+        assert!(matches!(
+            Stream::new("".as_bytes()).x_at(0),
+            Err(StreamError::InvalidCycle { cycle: 0 })
+        ));
+    }
+
+    #[test]
+    fn stream_long_program_test() {
+        let count = 1_000_000;
+        let reader = BufReader::new(Generator {
+            count,
+            pending: vec![],
+        });
+        let mut stream = Stream::new(reader);
+        assert_eq!(stream.x_at(4).unwrap(), 2);
+        assert_eq!(stream.x_at(3 * count).unwrap(), count as i32 + 1);
+        assert!(matches!(
+            stream.x_at(3 * count + 1),
+            Err(StreamError::PastEnd { .. })
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+
+use super::{
+    assembler::assemble,
+    ocr::{glyph, recognize, OcrConfig, CELL_WIDTH, GLYPH_HEIGHT},
+    run_on_crt, CrtConfig, MAX_CYCLES,
+};
+
+pub const MAX_LETTERS: usize = 8;
+
+// Every X outside this range hides the sprite from the whole screen, so it is enough
+// to consider the values in it.
+const MIN_X: i32 = -2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SynthesisError {
+    TooLong(usize),
+    UnsupportedLetter(char),
+    // X starts at 1 and only changes after an `addx` completes, which constrains the
+    // pixels that can be drawn.
+    Undrawable { row: usize, col: usize },
+    Mismatch(String),
+}
+
+impl fmt::Display for SynthesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong(len) => write!(
+                f,
+                "at most {} letters fit on the screen, got {}",
+                MAX_LETTERS, len
+            ),
+            Self::UnsupportedLetter(letter) => write!(f, "no glyph for {:?}", letter),
+            Self::Undrawable { row, col } => {
+                write!(f, "pixel at row {} col {} cannot be drawn", row, col)
+            }
+            Self::Mismatch(text) => write!(f, "the program draws {:?}", text),
+        }
+    }
+}
+
+// SplitMix64, good enough to pick among valid programs.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_4476_D1CE_4E5B);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Lit pixels of the screen, one per cycle.
+fn target_pixels(text: &str, config: &CrtConfig) -> Result<Vec<bool>, SynthesisError> {
+    let len = text.chars().count();
+    if len > MAX_LETTERS.min(config.width / CELL_WIDTH) {
+        return Err(SynthesisError::TooLong(len));
+    }
+    let mut pixels = vec![false; config.width * config.height];
+    for (i, letter) in text.chars().enumerate() {
+        let rows = glyph(letter).ok_or(SynthesisError::UnsupportedLetter(letter))?;
+        for (row, line) in rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                pixels[row * config.width + i * CELL_WIDTH + col] = c == '#';
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+struct Synthesizer {
+    pixels: Vec<bool>,
+    width: usize,
+    x_values: usize,
+    // feasible[cycle][x]: the screen can be completed when an instruction starts at
+    // `cycle` (0 based) with X equal to `x`
+    feasible: Vec<Vec<bool>>,
+}
+
+impl Synthesizer {
+    fn new(pixels: Vec<bool>, config: &CrtConfig) -> Self {
+        let width = config.width;
+        let x_values = (width as i32 + 2 - MIN_X) as usize;
+        let cycles = pixels.len();
+        let mut synthesizer = Self {
+            pixels,
+            width,
+            x_values,
+            feasible: vec![vec![true; x_values]; cycles + 2],
+        };
+        for cycle in (0..cycles).rev() {
+            let any_after_addx = synthesizer.feasible[cycle + 2].contains(&true);
+            for x in 0..x_values {
+                let x_value = x as i32 + MIN_X;
+                synthesizer.feasible[cycle][x] = synthesizer.draws(cycle, x_value)
+                    && (synthesizer.feasible[cycle + 1][x]
+                        || (synthesizer.draws(cycle + 1, x_value) && any_after_addx));
+            }
+        }
+        synthesizer
+    }
+
+    // Whether `x` draws the right pixel during `cycle`. Cycles past the screen draw
+    // nothing.
+    fn draws(&self, cycle: usize, x: i32) -> bool {
+        let Some(&lit) = self.pixels.get(cycle) else {
+            return true;
+        };
+        let col = (cycle % self.width) as i32;
+        ((col - x).abs() <= 1) == lit
+    }
+
+    fn index(x: i32) -> usize {
+        (x.max(MIN_X) - MIN_X) as usize
+    }
+
+    // The first pixel missed by every program, found by following the states reachable
+    // from the start.
+    fn undrawable_pixel(&self) -> SynthesisError {
+        let cycles = self.pixels.len();
+        let mut reachable = vec![vec![false; self.x_values]; cycles + 2];
+        reachable[0][Self::index(1)] = true;
+        let mut last = 0;
+        for cycle in 0..cycles {
+            for x in 0..self.x_values {
+                let x_value = x as i32 + MIN_X;
+                if !reachable[cycle][x] || !self.draws(cycle, x_value) {
+                    continue;
+                }
+                last = last.max(cycle + 1);
+                reachable[cycle + 1][x] = true;
+                if self.draws(cycle + 1, x_value) {
+                    reachable[cycle + 2] = vec![true; self.x_values];
+                }
+            }
+        }
+        SynthesisError::Undrawable {
+            row: last / self.width,
+            col: last % self.width,
+        }
+    }
+
+    fn program(&self, seed: u64) -> Result<String, SynthesisError> {
+        let mut x = 1;
+        if !self.feasible[0][Self::index(x)] {
+            return Err(self.undrawable_pixel());
+        }
+        let mut rng = Rng(seed);
+        let mut program = vec![];
+        let mut cycle = 0;
+        while cycle < self.pixels.len() {
+            let noop = self.feasible[cycle + 1][Self::index(x)];
+            let targets: Vec<_> = if self.draws(cycle + 1, x) {
+                (0..self.x_values)
+                    .filter(|&next| self.feasible[cycle + 2][next])
+                    .collect()
+            } else {
+                vec![]
+            };
+            if noop && (targets.is_empty() || rng.below(2) == 0) {
+                program.push("noop".to_owned());
+                cycle += 1;
+            } else {
+                let next = targets[rng.below(targets.len())] as i32 + MIN_X;
+                program.push(format!("addx {}", next - x));
+                x = next;
+                cycle += 2;
+            }
+        }
+        Ok(program.join("\n") + "\n")
+    }
+}
+
+// Generates a program of `addx` and `noop` whose output on the default CRT spells
+// `text`. Different seeds give different programs.
+pub fn synthesize(text: &str, seed: u64) -> Result<String, SynthesisError> {
+    let config = CrtConfig::default();
+    debug_assert!(config.height == GLYPH_HEIGHT && config.sprite_width == 3);
+    let synthesizer = Synthesizer::new(target_pixels(text, &config)?, &config);
+    let source = synthesizer.program(seed)?;
+    let program = assemble(&source).expect("Generated programs are valid");
+    let crt = run_on_crt(&program, config, MAX_CYCLES).expect("Generated programs do not jump");
+    match recognize(&crt.display(), OcrConfig::default()) {
+        Ok(drawn) if drawn == text => Ok(source),
+        Ok(drawn) => Err(SynthesisError::Mismatch(drawn)),
+        Err(e) => Err(SynthesisError::Mismatch(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_10::synthesizer::*;
+
+    #[test]
+    fn synthesizer_synthesize_test() {
+        let first = synthesize("FJUBULRZ", 1).unwrap();
+        let second = synthesize("FJUBULRZ", 2).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(synthesize("FJUBULRZ", 1).unwrap(), first);
+        assert!(first
+            .lines()
+            .all(|ln| ln == "noop" || ln.starts_with("addx ")));
+        assert!(synthesize("BRR", 7).is_ok());
+    }
+
+    #[test]
+    fn synthesizer_errors_test() {
+        // The first two pixels are drawn while X is still 1
+        assert_eq!(
+            synthesize("HELLO", 0),
+            Err(SynthesisError::Undrawable { row: 0, col: 1 })
+        );
+        assert_eq!(synthesize("ABCDEFGHI", 0), Err(SynthesisError::TooLong(9)));
+        assert_eq!(
+            synthesize("BAD", 0),
+            Err(SynthesisError::UnsupportedLetter('D'))
+        );
+    }
+}
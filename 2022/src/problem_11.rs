@@ -1,8 +1,29 @@
 #![allow(dead_code)]
 
-use std::{collections::HashSet, fmt::Debug, str::FromStr};
+mod expression;
+mod parser;
+mod report;
+mod rules;
+mod worry;
 
-use crate::utilities::read_file;
+use std::{collections::HashMap, fmt};
+
+use num_bigint::BigUint;
+
+use crate::{
+    recording::Simulation,
+    trace::trace_event,
+    utilities::{number_theory::lcm_all, read_file},
+};
+
+use expression::{BinaryOp, EvalError, Expr};
+use parser::parse_monkeys;
+pub use report::ReportFormat;
+use report::{build_report, ReportOptions};
+pub use rules::RuleOverrides;
+use rules::{ReliefRule, Rules, ThrowOrder};
+pub use worry::Representation;
+use worry::Worry;
 
 fn get_input() -> String {
     // read_file("problem_11_sample")
@@ -10,21 +31,77 @@ fn get_input() -> String {
 }
 
 type Integral = u64;
-type MonkeyIndexWithItem = (usize, Integral);
-type ItemsToThrow = Vec<MonkeyIndexWithItem>;
+type MonkeyIndexWithItem<W> = (usize, W);
+type ItemsToThrow<W> = Vec<MonkeyIndexWithItem<W>>;
 
-#[derive(Debug, Clone)]
-enum Operation {
-    AddIntegral(Integral),
-    AddOld,
-    MultiplyIntegral(Integral),
-    MultiplyOld,
+// How worry levels go down after an inspection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relief {
+    None,
+    Divide(Integral),
+    // Keeps the remainder, which preserves every divisibility test when the modulus
+    // is a multiple of all the divisors
+    Modulo(Integral),
+}
+
+impl Relief {
+    fn apply<W: Worry>(self, worry: W) -> Result<W, EvalError> {
+        match self {
+            Self::None => Ok(worry),
+            Self::Divide(divisor) => worry.apply(BinaryOp::Div, &W::from(divisor)),
+            Self::Modulo(0) => Err(EvalError::DivisionByZero),
+            Self::Modulo(modulus) => Ok(W::from(worry.rem_integral(modulus))),
+        }
+    }
+}
+
+// Which item an error is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemPosition {
+    // Index in the items the inspecting monkey holds at the start of its turn, when
+    // the whole game is played
+    Held(usize),
+    // A starting item, as the monkey holding it at the start of the game and its
+    // index there, when items are followed on their own
+    Starting { monkey: usize, index: usize },
+}
+
+impl fmt::Display for ItemPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Held(index) => write!(f, "item #{}", index),
+            Self::Starting { monkey, index } => {
+                write!(f, "starting item #{} of monkey {}", index, monkey)
+            }
+        }
+    }
+}
+
+// An inspection that failed, by overflowing or dividing by zero. `monkey` is the
+// inspecting monkey and `item` the worry level the item had before the inspection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundError {
+    pub round: usize,
+    pub monkey: usize,
+    pub position: ItemPosition,
+    pub item: String,
+    pub error: EvalError,
+}
+
+impl fmt::Display for RoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round {}, monkey {}, {} with worry level {}: {}",
+            self.round, self.monkey, self.position, self.item, self.error
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
-struct Monkey {
-    items: Vec<Integral>,
-    operation: Operation,
+struct Monkey<W = Integral> {
+    items: Vec<W>,
+    operation: Expr,
     divisibility_check: Integral,
     monkey_true: usize,
     monkey_false: usize,
@@ -32,143 +109,444 @@ struct Monkey {
 }
 
 impl Monkey {
-    fn new() -> Self {
-        Self {
-            items: vec![],
-            operation: Operation::AddOld,
-            divisibility_check: 1,
-            monkey_true: 0,
-            monkey_false: 0,
-            inspected: 0,
+    fn with_worry<W: Worry>(&self) -> Monkey<W> {
+        Monkey {
+            items: self.items.iter().map(|&item| W::from(item)).collect(),
+            operation: self.operation.clone(),
+            divisibility_check: self.divisibility_check,
+            monkey_true: self.monkey_true,
+            monkey_false: self.monkey_false,
+            inspected: self.inspected,
         }
     }
+}
 
-    fn play(&mut self, stess_reducer: impl Fn(Integral) -> Integral) -> ItemsToThrow {
+impl<W: Worry> Monkey<W> {
+    // On failure, returns the index of the item and the error, and leaves the monkey
+    // as it was.
+    fn play(
+        &mut self,
+        relief: Relief,
+        order: ThrowOrder,
+    ) -> Result<ItemsToThrow<W>, (usize, EvalError)> {
+        let to_throw = self.inspect_items(relief, order)?;
         self.inspected += self.items.len() as u64;
-        let to_throw = self.inspect_items(stess_reducer);
         self.items.clear();
-        to_throw
-    }
-
-    fn inspect_items(&mut self, stess_reducer: impl Fn(Integral) -> Integral) -> ItemsToThrow {
-        self.items
-            .iter()
-            .map(|&item| {
-                let item = match self.operation {
-                    Operation::AddOld => item * 2,
-                    Operation::AddIntegral(n) => item + n,
-                    Operation::MultiplyOld => item * item,
-                    Operation::MultiplyIntegral(n) => item * n,
-                };
-                let item = stess_reducer(item);
-                let id = if item % self.divisibility_check == 0 {
-                    self.monkey_true
-                } else {
-                    self.monkey_false
-                };
-                (id, item)
-            })
-            .collect()
-    }
-
-    fn catch_item(&mut self, item: Integral) {
+        Ok(to_throw)
+    }
+
+    fn inspect_items(
+        &self,
+        relief: Relief,
+        order: ThrowOrder,
+    ) -> Result<ItemsToThrow<W>, (usize, EvalError)> {
+        let inspect = |(i, item): (usize, &W)| {
+            let item = self
+                .operation
+                .eval(item)
+                .and_then(|item| relief.apply(item))
+                .map_err(|e| (i, e))?;
+            let id = if item.rem_integral(self.divisibility_check) == 0 {
+                self.monkey_true
+            } else {
+                self.monkey_false
+            };
+            Ok((id, item))
+        };
+        let items = self.items.iter().enumerate();
+        match order {
+            ThrowOrder::Fifo => items.map(inspect).collect(),
+            ThrowOrder::Lifo => items.rev().map(inspect).collect(),
+        }
+    }
+
+    fn catch_item(&mut self, item: W) {
         self.items.push(item);
     }
 }
 
-fn split_at_colon(ln: &str) -> (&str, &str) {
-    ln.split_once(": ").unwrap()
+fn parse_input() -> Vec<Monkey> {
+    parse_monkeys(&get_input()).unwrap_or_else(|errors| {
+        let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        panic!("Invalid monkeys:\n{}", errors.join("\n"))
+    })
+}
+
+fn inspections<W>(monkeys: &[Monkey<W>]) -> Vec<u64> {
+    monkeys.iter().map(|m| m.inspected).collect()
 }
 
-fn parse_operation(operation: &str) -> Operation {
-    let rhs = operation.split_once(" = ").unwrap().1;
-    let parts: Vec<_> = rhs.splitn(3, ' ').collect();
-    let operand = parts[1];
-    let value = parts[2];
-    match (operand, value) {
-        ("+", "old") => Operation::AddOld,
-        ("+", n) => Operation::AddIntegral(n.parse().unwrap()),
-        ("*", "old") => Operation::MultiplyOld,
-        ("*", n) => Operation::MultiplyIntegral(n.parse().unwrap()),
-        (x, y) => panic!("Invalid operation: {} {}", x, y),
+// `round` is only used to report errors. On an error, the monkeys before the one
+// that failed have already thrown their items, so `monkeys` is left in the middle of
+// the round.
+fn play_round<W: Worry>(
+    monkeys: &mut [Monkey<W>],
+    round: usize,
+    rules: &Rules,
+) -> Result<(), RoundError> {
+    for i in 0..monkeys.len() {
+        let indexed_items =
+            monkeys[i]
+                .play(rules.relief, rules.order)
+                .map_err(|(index, error)| RoundError {
+                    round,
+                    monkey: i,
+                    position: ItemPosition::Held(index),
+                    item: monkeys[i].items[index].to_string(),
+                    error,
+                })?;
+        for (id, item) in indexed_items {
+            trace_event!(Trace, "item thrown", from = i, to = id, worry = item);
+            monkeys[id].catch_item(item);
+        }
     }
+    Ok(())
 }
 
-fn get_last_integer<I: FromStr>(ln: &str) -> I
-where
-    I::Err: Debug,
-{
-    ln.split_whitespace().last().unwrap().parse().unwrap()
+// Inspections of each monkey after the rounds of `rules`
+fn generic_solution<W: Worry>(
+    mut monkeys: Vec<Monkey<W>>,
+    rules: &Rules,
+) -> Result<Vec<u64>, RoundError> {
+    for round in 1..=rules.rounds {
+        play_round(&mut monkeys, round, rules)?;
+        trace_event!(
+            Debug,
+            "round played",
+            round = round,
+            inspected = format!("{:?}", inspections(&monkeys))
+        );
+    }
+    Ok(inspections(&monkeys))
 }
 
-fn parse_monkey(monkey_txt: &str) -> Monkey {
-    let mut monkey = Monkey::new();
-    monkey_txt
-        .lines()
-        .skip(1)
-        .map(|ln| ln.trim_start())
-        .for_each(|ln| {
-            let (prefix, value) = split_at_colon(ln);
-            match prefix {
-                "Starting items" => {
-                    monkey.items = value.split(", ").map(|n| n.parse().unwrap()).collect()
-                }
-                "Operation" => monkey.operation = parse_operation(value),
-                "Test" => monkey.divisibility_check = get_last_integer::<Integral>(value),
-                "If true" => monkey.monkey_true = get_last_integer::<usize>(value),
-                "If false" => monkey.monkey_false = get_last_integer::<usize>(value),
-                _ => panic!("Invalid input line {}", ln),
-            };
-        });
-    monkey
+fn solve_with(
+    monkeys: &[Monkey],
+    rules: &Rules,
+    representation: Representation,
+) -> Result<Vec<u64>, RoundError> {
+    fn convert<W: Worry>(monkeys: &[Monkey]) -> Vec<Monkey<W>> {
+        monkeys.iter().map(Monkey::with_worry).collect()
+    }
+    match representation {
+        Representation::U64 => generic_solution(convert::<u64>(monkeys), rules),
+        Representation::U128 => generic_solution(convert::<u128>(monkeys), rules),
+        Representation::Big => generic_solution(convert::<BigUint>(monkeys), rules),
+    }
 }
 
-fn parse_input() -> Vec<Monkey> {
-    get_input()
-        .split_terminator("\r\n\r\n")
-        .map(parse_monkey)
-        .collect()
-}
-
-fn compute_monkey_business(mut monkeys: Vec<Monkey>) -> u64 {
-    monkeys.sort_by(|ma, mb| mb.inspected.cmp(&ma.inspected));
-    monkeys[0].inspected * monkeys[1].inspected
-}
-
-fn generic_solution<StressReducer>(
-    mut monkeys: Vec<Monkey>,
-    rounds: usize,
-    stress_reducer: StressReducer,
-) -> Integral
-where
-    StressReducer: Fn(Integral) -> Integral + Copy,
-{
-    for _ in 0..rounds {
-        for i in 0..monkeys.len() {
-            let indexed_items = monkeys[i].play(stress_reducer);
-            for (id, item) in indexed_items {
-                monkeys[id].catch_item(item);
+// Uses the smallest representation that does not overflow, moving to a larger one
+// and starting over when it does.
+fn solve(monkeys: &[Monkey], rules: &Rules) -> Result<(Vec<u64>, Representation), RoundError> {
+    let mut representations = [Representation::U64, Representation::U128].into_iter();
+    for representation in representations.by_ref() {
+        match solve_with(monkeys, rules, representation) {
+            Ok(inspected) => return Ok((inspected, representation)),
+            Err(overflow) if overflow.error == EvalError::Overflow => {
+                trace_event!(
+                    Info,
+                    "representation too small",
+                    representation = representation,
+                    overflow = overflow
+                )
             }
+            Err(e) => return Err(e),
         }
     }
-    compute_monkey_business(monkeys)
+    solve_with(monkeys, rules, Representation::Big)
+        .map(|inspected| (inspected, Representation::Big))
 }
 
-fn solution_part_1() -> Integral {
+// Monkey business with the given rules, and the representation that was needed. The
+// representation is chosen automatically when not given.
+fn business(
+    monkeys: &[Monkey],
+    rules: &Rules,
+    representation: Option<Representation>,
+) -> Result<(u128, Representation), String> {
+    let (inspected, representation) = match representation {
+        Some(representation) => {
+            solve_with(monkeys, rules, representation).map(|inspected| (inspected, representation))
+        }
+        None => solve(monkeys, rules),
+    }
+    .map_err(|e| format!("Failed at {}", e))?;
+    Ok((rules.metric.apply(&inspected)?, representation))
+}
+
+pub fn solution_part_1() -> u128 {
     let monkeys = parse_input();
-    generic_solution(monkeys, 20, |stress| stress / 3)
+    let rules = part_rules(1, &monkeys).unwrap_or_else(|e| panic!("{}", e));
+    business(&monkeys, &rules, None)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .0
 }
 
-fn solution_part_2() -> Integral {
+// The smallest modulus keeping every divisibility test, the lcm of the divisors.
+fn common_modulus<W>(monkeys: &[Monkey<W>]) -> Result<Integral, String> {
+    lcm_all(monkeys.iter().map(|m| m.divisibility_check))
+        .map_err(|e| format!("Cannot compute the lcm of the divisors: {}", e))
+}
+
+pub fn solution_part_2() -> u128 {
     let monkeys = parse_input();
-    let gcd: HashSet<_> = monkeys.iter().map(|m| m.divisibility_check).collect();
-    let gcd = gcd.iter().fold(1, |acc, n| acc * n);
-    generic_solution(monkeys, 10_000, |stress| stress % gcd)
+    let rules = part_rules(2, &monkeys).unwrap_or_else(|e| panic!("{}", e));
+    business(&monkeys, &rules, None)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .0
+}
+
+// Plays the rounds of a single item starting from `state` (monkey, worry level), until
+// it is thrown to a monkey that plays before the current one. Adds the inspections to
+// `inspected` and returns the state for the next round.
+fn play_item_round(
+    monkeys: &[Monkey],
+    (mut monkey, mut worry): (usize, Integral),
+    relief: Relief,
+    inspected: &mut [u64],
+) -> Result<(usize, Integral), (usize, Integral, EvalError)> {
+    loop {
+        let current = &monkeys[monkey];
+        inspected[monkey] += 1;
+        let next = current
+            .operation
+            .eval(&worry)
+            .and_then(|item| relief.apply(item))
+            .map_err(|e| (monkey, worry, e))?;
+        let target = if next.is_multiple_of(current.divisibility_check) {
+            current.monkey_true
+        } else {
+            current.monkey_false
+        };
+        if target <= monkey {
+            return Ok((target, next));
+        }
+        (monkey, worry) = (target, next);
+    }
+}
+
+// Inspections per monkey after `rounds` rounds. Items never affect each other, and
+// with a modulo relief the state of an item at the start of a round can only take
+// finitely many values, so each item is followed until its state repeats and the
+// rest of its rounds are extrapolated. Errors report the starting item.
+fn inspections_with_cycles(
+    monkeys: &[Monkey],
+    rounds: u64,
+    relief: Relief,
+) -> Result<Vec<u64>, RoundError> {
+    let starting_items = monkeys.iter().enumerate().flat_map(|(i, monkey)| {
+        monkey.items.iter().enumerate().map(move |(index, &item)| {
+            let position = ItemPosition::Starting { monkey: i, index };
+            (position, (i, item))
+        })
+    });
+    let mut total = vec![0u64; monkeys.len()];
+    for (position, mut state) in starting_items {
+        let mut seen: HashMap<(usize, Integral), usize> = HashMap::new();
+        // Inspections of the item after each round
+        let mut history = vec![vec![0u64; monkeys.len()]];
+        let inspected = loop {
+            let round = history.len() as u64 - 1;
+            if round == rounds {
+                break history.pop().unwrap();
+            }
+            if let Some(&start) = seen.get(&state) {
+                let period = round - start as u64;
+                let (cycles, rest) = ((rounds - round) / period, (rounds - round) % period);
+                trace_event!(
+                    Debug,
+                    "cycle found",
+                    item = position,
+                    start = start,
+                    period = period
+                );
+                let (first, last) = (&history[start], &history[round as usize]);
+                let partial = &history[start + rest as usize];
+                break (0..monkeys.len())
+                    .map(|i| last[i] + cycles * (last[i] - first[i]) + partial[i] - first[i])
+                    .collect();
+            }
+            seen.insert(state, history.len() - 1);
+            let mut inspected = history.last().unwrap().clone();
+            state = play_item_round(monkeys, state, relief, &mut inspected).map_err(
+                |(monkey, item, error)| RoundError {
+                    round: round as usize + 1,
+                    monkey,
+                    position,
+                    item: item.to_string(),
+                    error,
+                },
+            )?;
+            history.push(inspected);
+        };
+        for (total, inspected) in total.iter_mut().zip(inspected) {
+            *total += inspected;
+        }
+    }
+    Ok(total)
+}
+
+// Rules of a part of the puzzle
+fn part_rules(part: u32, monkeys: &[Monkey]) -> Result<Rules, String> {
+    match part {
+        1 => Ok(Rules::new(20, Relief::Divide(3))),
+        2 => Ok(Rules::new(10_000, Relief::Modulo(common_modulus(monkeys)?))),
+        _ => Err(format!("Invalid part {}", part)),
+    }
+}
+
+fn rules_with(part: u32, overrides: &RuleOverrides, monkeys: &[Monkey]) -> Result<Rules, String> {
+    let rules = part_rules(part, monkeys)?;
+    let relief = match overrides.relief {
+        None => rules.relief,
+        Some(ReliefRule::None) => Relief::None,
+        Some(ReliefRule::Divide(divisor)) => Relief::Divide(divisor),
+        Some(ReliefRule::Modulo(Some(modulus))) => Relief::Modulo(modulus),
+        Some(ReliefRule::Modulo(None)) => Relief::Modulo(common_modulus(monkeys)?),
+    };
+    Ok(Rules {
+        rounds: overrides.rounds.unwrap_or(rules.rounds),
+        relief,
+        order: overrides.order.unwrap_or(rules.order),
+        metric: overrides.metric.unwrap_or(rules.metric),
+    })
+}
+
+// Monkey business with the rules of the given part and the overrides, and the
+// representation that was needed. The representation is chosen automatically when
+// not given.
+pub fn monkey_business(
+    part: u32,
+    overrides: &RuleOverrides,
+    representation: Option<Representation>,
+) -> Result<(u128, Representation), String> {
+    let monkeys = parse_input();
+    business(
+        &monkeys,
+        &rules_with(part, overrides, &monkeys)?,
+        representation,
+    )
+}
+
+// Monkey business after any number of rounds. Only a modulo relief keeps the worry
+// levels bounded, so the states of the items repeat. The throw order does not change
+// the inspections.
+pub fn monkey_business_with_cycles(part: u32, overrides: &RuleOverrides) -> Result<u128, String> {
+    let monkeys = parse_input();
+    let rules = rules_with(part, overrides, &monkeys)?;
+    if !matches!(rules.relief, Relief::Modulo(_)) {
+        return Err("Cycle detection needs a modulo relief".to_owned());
+    }
+    let inspected = inspections_with_cycles(&monkeys, rules.rounds as u64, rules.relief)
+        .map_err(|e| format!("Failed at {}", e))?;
+    rules.metric.apply(&inspected)
+}
+
+// Per-round inspections, the items held after the `snapshots` rounds and the path of
+// a starting item, with the rules of the given part and the overrides.
+pub fn report(
+    part: u32,
+    overrides: &RuleOverrides,
+    snapshots: Vec<usize>,
+    item: Option<(usize, usize)>,
+    format: ReportFormat,
+) -> Result<String, String> {
+    let monkeys = parse_input();
+    let rules = rules_with(part, overrides, &monkeys)?;
+    let options = ReportOptions { snapshots, item };
+    Ok(build_report(&monkeys, &rules, &options)?.render(format))
+}
+
+#[derive(Clone)]
+pub struct MonkeySimulation {
+    monkeys: Vec<Monkey>,
+    round: usize,
+    rules: Rules,
+    error: Option<RoundError>,
+}
+
+impl Simulation for MonkeySimulation {
+    // A step plays a whole round. The simulation stops at the first error, in the
+    // middle of the round.
+    fn step(&mut self) -> bool {
+        if self.round == self.rules.rounds || self.error.is_some() {
+            return false;
+        }
+        if let Err(error) = play_round(&mut self.monkeys, self.round + 1, &self.rules) {
+            self.error = Some(error);
+            return false;
+        }
+        self.round += 1;
+        true
+    }
+
+    // Same wording as the puzzle text.
+    fn frame(&self) -> String {
+        let mut lines = vec![format!("== After round {} ==", self.round)];
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            let items: Vec<_> = monkey.items.iter().map(|item| item.to_string()).collect();
+            lines.push(
+                format!("Monkey {}: {}", i, items.join(", "))
+                    .trim_end()
+                    .to_owned(),
+            );
+        }
+        lines.push("".to_owned());
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            lines.push(format!(
+                "Monkey {} inspected items {} times.",
+                i, monkey.inspected
+            ));
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("Failed at {}", error));
+        }
+        lines.join("\n")
+    }
+
+    fn inspect(&self) -> String {
+        let mut lines = vec![format!("round {} of {}", self.round, self.rules.rounds)];
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            lines.push(format!(
+                "Monkey {}: items {:?}, operation new = {}, divisible by {} ? {} : {}, inspected {}",
+                i,
+                monkey.items,
+                monkey.operation,
+                monkey.divisibility_check,
+                monkey.monkey_true,
+                monkey.monkey_false,
+                monkey.inspected
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn simulation(part: u32) -> MonkeySimulation {
+    let monkeys = parse_input();
+    let rules = part_rules(part, &monkeys).unwrap_or_else(|e| panic!("{}", e));
+    MonkeySimulation {
+        monkeys,
+        round: 0,
+        rules,
+        error: None,
+    }
+}
+
+pub fn simulation_part_1() -> MonkeySimulation {
+    simulation(1)
+}
+
+pub fn simulation_part_2() -> MonkeySimulation {
+    simulation(2)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::problem_11::*;
+    use crate::{
+        problem_11::{rules::Metric, *},
+        utilities::read_file,
+    };
 
     #[test]
     fn problem_11_solution_part_1_test() {
@@ -179,4 +557,205 @@ mod tests {
     fn problem_11_solution_part_2_test() {
         println!("problem 11 solution 2: {}", solution_part_2());
     }
+
+    fn sample_monkeys() -> Vec<Monkey> {
+        parse_monkeys(&read_file("problem_11_sample")).unwrap()
+    }
+
+    // Every item goes through monkey 0, which squares it, once per round
+    const SQUARING: &str = "\
+Monkey 0:
+  Starting items: 2, 3
+  Operation: new = old * old
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+
+    #[test]
+    fn problem_11_solve_test() {
+        let monkeys = sample_monkeys();
+        assert_eq!(
+            business(&monkeys, &part_rules(1, &monkeys).unwrap(), None),
+            Ok((10605, Representation::U64))
+        );
+        assert_eq!(
+            business(&monkeys, &part_rules(2, &monkeys).unwrap(), None),
+            Ok((2713310158, Representation::U64))
+        );
+    }
+
+    #[test]
+    fn problem_11_rules_test() {
+        let monkeys = sample_monkeys();
+        let overrides = RuleOverrides {
+            rounds: Some(1),
+            relief: Some(ReliefRule::Modulo(None)),
+            order: Some(ThrowOrder::Lifo),
+            metric: Some(Metric::Sum),
+        };
+        let rules = rules_with(1, &overrides, &monkeys).unwrap();
+        assert_eq!(
+            rules,
+            Rules {
+                rounds: 1,
+                relief: Relief::Modulo(96577),
+                order: ThrowOrder::Lifo,
+                metric: Metric::Sum,
+            }
+        );
+        assert_eq!(
+            rules_with(2, &RuleOverrides::default(), &monkeys),
+            part_rules(2, &monkeys)
+        );
+        assert_eq!(
+            business(&monkeys, &rules, None),
+            Ok((15, Representation::U64))
+        );
+        // Items move independently, so the order only changes where they end up in
+        // the lists
+        let (mut fifo, mut lifo) = (monkeys.clone(), monkeys);
+        let rules = Rules::new(1, Relief::Divide(3));
+        play_round(&mut fifo, 1, &rules).unwrap();
+        let rules = Rules {
+            order: ThrowOrder::Lifo,
+            ..rules
+        };
+        play_round(&mut lifo, 1, &rules).unwrap();
+        assert_eq!(inspections(&fifo), inspections(&lifo));
+        assert_eq!(fifo[1].items, vec![2080, 25, 167, 207, 401, 1046]);
+        assert_eq!(lifo[1].items, vec![2080, 401, 1046, 167, 207, 25]);
+    }
+
+    #[test]
+    fn problem_11_overflow_test() {
+        let monkeys = parse_monkeys(SQUARING).unwrap();
+        // 2^32 squared does not fit
+        let rules = Rules::new(8, Relief::None);
+        let overflow = solve_with(&monkeys, &rules, Representation::U64).unwrap_err();
+        assert_eq!(
+            overflow.to_string(),
+            "round 6, monkey 0, item #1 with worry level 4294967296: overflow"
+        );
+        assert_eq!(overflow.position, ItemPosition::Held(1));
+        let overflow = solve_with(&monkeys, &rules, Representation::U128).unwrap_err();
+        assert_eq!((overflow.round, overflow.monkey), (7, 0));
+        assert_eq!(
+            business(&monkeys, &Rules::new(6, Relief::None), None),
+            Ok((306, Representation::U128))
+        );
+        assert_eq!(
+            business(&monkeys, &rules, None),
+            Ok((552, Representation::Big))
+        );
+        let rules = Rules::new(1, Relief::Modulo(0));
+        let error = solve_with(&monkeys, &rules, Representation::U64);
+        assert_eq!(error.unwrap_err().error, EvalError::DivisionByZero);
+    }
+
+    #[test]
+    fn problem_11_common_modulus_test() {
+        let mut monkeys = sample_monkeys();
+        for (monkey, divisor) in monkeys.iter_mut().zip([4, 6, 4, 9]) {
+            monkey.divisibility_check = divisor;
+        }
+        assert_eq!(common_modulus(&monkeys), Ok(36));
+        // The remainders modulo the lcm keep every test, as exact arithmetic does
+        let exact = solve_with(&monkeys, &Rules::new(10, Relief::None), Representation::Big);
+        assert_eq!(
+            solve_with(
+                &monkeys,
+                &Rules::new(10, Relief::Modulo(36)),
+                Representation::U64
+            ),
+            exact
+        );
+        monkeys[0].divisibility_check = u64::MAX;
+        monkeys[1].divisibility_check = u64::MAX - 1;
+        assert!(common_modulus(&monkeys).is_err());
+    }
+
+    #[test]
+    fn problem_11_cycles_test() {
+        let monkeys = sample_monkeys();
+        let rules = part_rules(2, &monkeys).unwrap();
+        let relief = rules.relief;
+        for rounds in (0..60).chain([1000, 10_000]) {
+            let mut brute_force = monkeys.clone();
+            for round in 1..=rounds {
+                play_round(&mut brute_force, round, &rules).unwrap();
+            }
+            assert_eq!(
+                inspections_with_cycles(&monkeys, rounds as u64, relief).unwrap(),
+                inspections(&brute_force)
+            );
+        }
+        assert_eq!(
+            Metric::default().apply(&inspections_with_cycles(&monkeys, 10_000, relief).unwrap()),
+            Ok(2713310158)
+        );
+        assert_eq!(
+            monkey_business_with_cycles(2, &RuleOverrides::default()),
+            Ok(solution_part_2())
+        );
+        assert!(monkey_business_with_cycles(1, &RuleOverrides::default()).is_err());
+        // Every item is inspected at least once per round
+        let rounds = 1_000_000_000_000;
+        let huge = inspections_with_cycles(&monkeys, rounds, relief).unwrap();
+        let items = monkeys.iter().map(|m| m.items.len() as u64).sum::<u64>();
+        assert!(huge.iter().sum::<u64>() >= items * rounds);
+        // The 3 starting with monkey 1 overflows once monkey 0 has squared it 6 times
+        let monkeys = parse_monkeys(
+            "\
+Monkey 0:
+  Starting items: 1
+  Operation: new = old * old
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 1, 3
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+",
+        )
+        .unwrap();
+        let error = inspections_with_cycles(&monkeys, 10, Relief::Modulo(u64::MAX)).unwrap_err();
+        assert_eq!(
+            (error.round, error.monkey, error.position),
+            (
+                7,
+                0,
+                ItemPosition::Starting {
+                    monkey: 1,
+                    index: 1
+                }
+            )
+        );
+        assert_eq!(
+            error.to_string(),
+            "round 7, monkey 0, starting item #1 of monkey 1 with worry level 1853020188851841: overflow"
+        );
+    }
+
+    #[test]
+    fn problem_11_simulation_test() {
+        let mut simulation = simulation_part_1();
+        while simulation.step() {}
+        assert!(simulation.frame().starts_with("== After round 20 =="));
+        assert_eq!(
+            Metric::default().apply(&inspections(&simulation.monkeys)),
+            Ok(solution_part_1())
+        );
+    }
 }
@@ -0,0 +1,297 @@
+use std::{fmt, iter::Peekable, str::CharIndices, str::FromStr};
+
+use super::{worry::Worry, Integral};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+            Self::Rem => '%',
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div | Self::Rem => 2,
+        }
+    }
+
+    fn from_symbol(c: char) -> Option<Self> {
+        [Self::Add, Self::Sub, Self::Mul, Self::Div, Self::Rem]
+            .into_iter()
+            .find(|op| op.symbol() == c)
+    }
+}
+
+// The right hand side of a monkey operation, computing the new worry level from the
+// old one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Literal(Integral),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "overflow"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl Expr {
+    pub fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Self {
+        Self::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn eval<W: Worry>(&self, old: &W) -> Result<W, EvalError> {
+        match self {
+            Self::Old => Ok(old.clone()),
+            Self::Literal(n) => Ok(W::from(*n)),
+            Self::Binary(op, lhs, rhs) => lhs.eval(old)?.apply(*op, &rhs.eval(old)?),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+// Puzzle syntax, with only the parentheses needed to parse back the same tree.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Old => write!(f, "old"),
+            Self::Literal(n) => write!(f, "{}", n),
+            Self::Binary(op, lhs, rhs) => {
+                let precedence = |expr: &Expr| match expr {
+                    Self::Binary(op, ..) => op.precedence(),
+                    _ => u8::MAX,
+                };
+                lhs.fmt_operand(f, precedence(lhs) < op.precedence())?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.fmt_operand(f, precedence(rhs) <= op.precedence())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExprError {
+    UnexpectedEnd,
+    // `position` is the index of the character in the text
+    UnexpectedChar { position: usize, found: char },
+    InvalidLiteral(String),
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            Self::UnexpectedChar { position, found } => {
+                write!(f, "unexpected {:?} at position {}", found, position)
+            }
+            Self::InvalidLiteral(literal) => write!(f, "invalid number {}", literal),
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn unexpected(&mut self) -> ExprError {
+        match self.peek() {
+            Some((position, found)) => ExprError::UnexpectedChar { position, found },
+            None => ExprError::UnexpectedEnd,
+        }
+    }
+
+    // Left associative sequence of operands of the same precedence
+    fn binary(
+        &mut self,
+        precedence: u8,
+        operand: fn(&mut Self) -> Result<Expr, ExprError>,
+    ) -> Result<Expr, ExprError> {
+        let mut expr = operand(self)?;
+        while let Some(op) = self
+            .peek()
+            .and_then(|(_, c)| BinaryOp::from_symbol(c))
+            .filter(|op| op.precedence() == precedence)
+        {
+            self.chars.next();
+            expr = Expr::binary(op, expr, operand(self)?);
+        }
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<Expr, ExprError> {
+        self.binary(1, Self::term)
+    }
+
+    fn term(&mut self) -> Result<Expr, ExprError> {
+        self.binary(2, Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ExprError> {
+        match self.peek() {
+            Some((_, '(')) => {
+                self.chars.next();
+                let expr = self.expr()?;
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(expr)
+                    }
+                    _ => Err(self.unexpected()),
+                }
+            }
+            Some((start, c)) if c.is_ascii_alphanumeric() => {
+                let mut end = start;
+                while let Some(&(i, c)) = self.chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    self.chars.next();
+                }
+                match &self.text[start..end] {
+                    "old" => Ok(Expr::Old),
+                    word if word.bytes().all(|b| b.is_ascii_digit()) => word
+                        .parse()
+                        .map(Expr::Literal)
+                        .map_err(|_| ExprError::InvalidLiteral(word.to_owned())),
+                    _ => Err(ExprError::UnexpectedChar {
+                        position: start,
+                        found: c,
+                    }),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            text: s,
+            chars: s.char_indices().peekable(),
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => Err(parser.unexpected()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_11::expression::*;
+
+    #[test]
+    fn expression_parse_test() {
+        for text in [
+            "old * 19",
+            "old + old",
+            "old - 3",
+            "old * 2 + 1",
+            "(old + 1) * old",
+            "old - (3 - 1)",
+            "old / 2 % 7",
+            "old * (old % 5)",
+        ] {
+            assert_eq!(text.parse::<Expr>().unwrap().to_string(), text);
+        }
+        assert_eq!(
+            "( (old) +1)*2".parse::<Expr>().unwrap().to_string(),
+            "(old + 1) * 2"
+        );
+        assert_eq!(
+            "old * 2 + 1".parse::<Expr>().unwrap(),
+            Expr::binary(
+                BinaryOp::Add,
+                Expr::binary(BinaryOp::Mul, Expr::Old, Expr::Literal(2)),
+                Expr::Literal(1)
+            )
+        );
+    }
+
+    #[test]
+    fn expression_parse_errors_test() {
+        assert_eq!("old +".parse::<Expr>(), Err(ExprError::UnexpectedEnd));
+        assert_eq!(
+            "old ^ 2".parse::<Expr>(),
+            Err(ExprError::UnexpectedChar {
+                position: 4,
+                found: '^'
+            })
+        );
+        assert_eq!("(old + 1".parse::<Expr>(), Err(ExprError::UnexpectedEnd));
+        assert_eq!(
+            "new * 2".parse::<Expr>(),
+            Err(ExprError::UnexpectedChar {
+                position: 0,
+                found: 'n'
+            })
+        );
+        assert_eq!(
+            "old + 99999999999999999999".parse::<Expr>(),
+            Err(ExprError::InvalidLiteral("99999999999999999999".to_owned()))
+        );
+    }
+
+    #[test]
+    fn expression_eval_test() {
+        let expr: Expr = "(old + 1) * old - 3".parse().unwrap();
+        assert_eq!(expr.eval(&4u64), Ok(17));
+        assert_eq!(expr.eval(&1u64), Err(EvalError::Overflow));
+        let square: Expr = "old * old".parse().unwrap();
+        assert_eq!(square.eval(&(1u64 << 32)), Err(EvalError::Overflow));
+        assert_eq!(square.eval(&(1u128 << 32)), Ok(1 << 64));
+        assert_eq!(
+            "old % (old - 5)".parse::<Expr>().unwrap().eval(&5u64),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use std::fmt;
+
+use super::{
+    expression::{Expr, ExprError},
+    Integral, Monkey,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MonkeyErrorKind {
+    InvalidHeader(String),
+    HeaderOutOfOrder { expected: usize, found: usize },
+    UnknownField(String),
+    InvalidValue { field: &'static str, value: String },
+    InvalidOperation(ExprError),
+    ZeroDivisor,
+    MissingField(&'static str),
+    DuplicateField(&'static str),
+    UnknownTarget { field: &'static str, target: usize },
+    ThrowsToItself { field: &'static str },
+}
+
+impl fmt::Display for MonkeyErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader(header) => {
+                write!(f, "expected a `Monkey N:` header, found `{}`", header)
+            }
+            Self::HeaderOutOfOrder { expected, found } => {
+                write!(f, "expected monkey {}, found monkey {}", expected, found)
+            }
+            Self::UnknownField(field) => write!(f, "unknown field `{}`", field),
+            Self::InvalidValue { field, value } => {
+                write!(f, "invalid value `{}` for `{}`", value, field)
+            }
+            Self::InvalidOperation(e) => write!(f, "invalid operation: {}", e),
+            Self::ZeroDivisor => write!(f, "the test divides by zero"),
+            Self::MissingField(field) => write!(f, "missing `{}`", field),
+            Self::DuplicateField(field) => write!(f, "`{}` given more than once", field),
+            Self::UnknownTarget { field, target } => {
+                write!(
+                    f,
+                    "`{}` throws to monkey {}, which does not exist",
+                    field, target
+                )
+            }
+            Self::ThrowsToItself { field } => write!(f, "`{}` throws to the monkey itself", field),
+        }
+    }
+}
+
+// `block` is the index of the monkey block in the text, `line` the line number in the
+// whole text. Errors about a block as a whole point to its header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonkeyError {
+    pub block: usize,
+    pub line: usize,
+    pub kind: MonkeyErrorKind,
+}
+
+impl fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {}, line {}: {}", self.block, self.line, self.kind)
+    }
+}
+
+const STARTING_ITEMS: &str = "Starting items";
+const OPERATION: &str = "Operation";
+const TEST: &str = "Test";
+const IF_TRUE: &str = "If true";
+const IF_FALSE: &str = "If false";
+
+#[derive(Default)]
+struct Fields {
+    items: Option<Vec<Integral>>,
+    operation: Option<Expr>,
+    divisibility_check: Option<Integral>,
+    // Targets with the line they are given on
+    monkey_true: Option<(usize, usize)>,
+    monkey_false: Option<(usize, usize)>,
+}
+
+// The text after `prefix` parsed as a number.
+fn parse_suffix<T: std::str::FromStr>(
+    field: &'static str,
+    value: &str,
+    prefix: &str,
+) -> Result<T, MonkeyErrorKind> {
+    value
+        .strip_prefix(prefix)
+        .and_then(|n| n.trim().parse().ok())
+        .ok_or_else(|| MonkeyErrorKind::InvalidValue {
+            field,
+            value: value.to_owned(),
+        })
+}
+
+fn parse_items(value: &str) -> Result<Vec<Integral>, MonkeyErrorKind> {
+    if value.is_empty() {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|item| item.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| MonkeyErrorKind::InvalidValue {
+            field: STARTING_ITEMS,
+            value: value.to_owned(),
+        })
+}
+
+fn parse_operation(value: &str) -> Result<Expr, MonkeyErrorKind> {
+    let rhs = value
+        .strip_prefix("new =")
+        .ok_or_else(|| MonkeyErrorKind::InvalidValue {
+            field: OPERATION,
+            value: value.to_owned(),
+        })?;
+    rhs.parse().map_err(MonkeyErrorKind::InvalidOperation)
+}
+
+fn set<T>(slot: &mut Option<T>, field: &'static str, value: T) -> Result<(), MonkeyErrorKind> {
+    match slot.replace(value) {
+        Some(_) => Err(MonkeyErrorKind::DuplicateField(field)),
+        None => Ok(()),
+    }
+}
+
+fn parse_field(fields: &mut Fields, ln: &str, line: usize) -> Result<(), MonkeyErrorKind> {
+    let (name, value) = ln
+        .split_once(':')
+        .ok_or_else(|| MonkeyErrorKind::UnknownField(ln.to_owned()))?;
+    let value = value.trim();
+    match name {
+        STARTING_ITEMS => set(&mut fields.items, STARTING_ITEMS, parse_items(value)?),
+        OPERATION => set(&mut fields.operation, OPERATION, parse_operation(value)?),
+        TEST => match parse_suffix(TEST, value, "divisible by")? {
+            0 => Err(MonkeyErrorKind::ZeroDivisor),
+            n => set(&mut fields.divisibility_check, TEST, n),
+        },
+        IF_TRUE => {
+            let target = parse_suffix(IF_TRUE, value, "throw to monkey")?;
+            set(&mut fields.monkey_true, IF_TRUE, (target, line))
+        }
+        IF_FALSE => {
+            let target = parse_suffix(IF_FALSE, value, "throw to monkey")?;
+            set(&mut fields.monkey_false, IF_FALSE, (target, line))
+        }
+        _ => Err(MonkeyErrorKind::UnknownField(name.to_owned())),
+    }
+}
+
+// Lines of a block with their line numbers, the first one being the header.
+fn parse_block(
+    index: usize,
+    lines: &[(usize, &str)],
+    errors: &mut Vec<MonkeyError>,
+) -> Option<(Monkey, [(usize, usize); 2])> {
+    let error_count = errors.len();
+    let (header_line, header) = lines[0];
+    let mut error = |line, kind| {
+        errors.push(MonkeyError {
+            block: index,
+            line,
+            kind,
+        })
+    };
+    match header
+        .strip_prefix("Monkey ")
+        .and_then(|rest| rest.strip_suffix(':'))
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        Some(found) if found != index => error(
+            header_line,
+            MonkeyErrorKind::HeaderOutOfOrder {
+                expected: index,
+                found,
+            },
+        ),
+        Some(_) => (),
+        None => error(
+            header_line,
+            MonkeyErrorKind::InvalidHeader(header.to_owned()),
+        ),
+    }
+    let mut fields = Fields::default();
+    for &(line, ln) in &lines[1..] {
+        if let Err(kind) = parse_field(&mut fields, ln, line) {
+            error(line, kind);
+        }
+    }
+    let missing = [
+        (fields.items.is_none(), STARTING_ITEMS),
+        (fields.operation.is_none(), OPERATION),
+        (fields.divisibility_check.is_none(), TEST),
+        (fields.monkey_true.is_none(), IF_TRUE),
+        (fields.monkey_false.is_none(), IF_FALSE),
+    ];
+    for (_, field) in missing.into_iter().filter(|(missing, _)| *missing) {
+        error(header_line, MonkeyErrorKind::MissingField(field));
+    }
+    if errors.len() > error_count {
+        return None;
+    }
+    let (monkey_true, monkey_false) = (fields.monkey_true.unwrap(), fields.monkey_false.unwrap());
+    let monkey = Monkey {
+        items: fields.items.unwrap(),
+        operation: fields.operation.unwrap(),
+        divisibility_check: fields.divisibility_check.unwrap(),
+        monkey_true: monkey_true.0,
+        monkey_false: monkey_false.0,
+        inspected: 0,
+    };
+    Some((monkey, [monkey_true, monkey_false]))
+}
+
+// Parses the monkey blocks, separated by blank lines, and checks that they describe a
+// valid game. Every error found is returned.
+pub fn parse_monkeys(text: &str) -> Result<Vec<Monkey>, Vec<MonkeyError>> {
+    let mut blocks: Vec<Vec<(usize, &str)>> = vec![];
+    let mut previous_blank = true;
+    for (i, ln) in text.lines().enumerate() {
+        let ln = ln.trim();
+        if ln.is_empty() {
+            previous_blank = true;
+            continue;
+        }
+        if previous_blank {
+            blocks.push(vec![]);
+        }
+        previous_blank = false;
+        blocks.last_mut().unwrap().push((i + 1, ln));
+    }
+    let mut errors = vec![];
+    let parsed: Vec<_> = blocks
+        .iter()
+        .enumerate()
+        .map(|(index, lines)| parse_block(index, lines, &mut errors))
+        .collect();
+    for (index, parsed) in parsed.iter().enumerate() {
+        let Some((_, targets)) = parsed else {
+            continue;
+        };
+        for (&(target, line), field) in targets.iter().zip([IF_TRUE, IF_FALSE]) {
+            let kind = if target >= blocks.len() {
+                MonkeyErrorKind::UnknownTarget { field, target }
+            } else if target == index {
+                MonkeyErrorKind::ThrowsToItself { field }
+            } else {
+                continue;
+            };
+            errors.push(MonkeyError {
+                block: index,
+                line,
+                kind,
+            });
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort_by_key(|e| e.line);
+        return Err(errors);
+    }
+    Ok(parsed.into_iter().map(|parsed| parsed.unwrap().0).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_11::{expression::ExprError, parser::*},
+        utilities::read_file,
+    };
+
+    fn kinds(text: &str) -> Vec<(usize, usize, MonkeyErrorKind)> {
+        parse_monkeys(text)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| (e.block, e.line, e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn parser_sample_test() {
+        let monkeys = parse_monkeys(&read_file("problem_11_sample")).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[0].items, vec![79, 98]);
+        assert_eq!(monkeys[0].operation.to_string(), "old * 19");
+        assert_eq!(monkeys[0].operation.eval(&3u64), Ok(57));
+        assert_eq!(
+            (
+                monkeys[3].divisibility_check,
+                monkeys[3].monkey_true,
+                monkeys[3].monkey_false
+            ),
+            (17, 0, 1)
+        );
+        let monkeys = parse_monkeys(
+            "Monkey 0:\n\
+             Starting items:\n\
+             Operation: new = (old + 1) * old\n\
+             Test: divisible by 2\n\
+             If true: throw to monkey 1\n\
+             If false: throw to monkey 1\n\
+             \n\
+             Monkey 1:\n\
+             Starting items: 4\n\
+             Operation: new = old\n\
+             Test: divisible by 3\n\
+             If true: throw to monkey 0\n\
+             If false: throw to monkey 0\n",
+        )
+        .unwrap();
+        assert!(monkeys[0].items.is_empty());
+        assert_eq!(monkeys[0].operation.eval(&3u64), Ok(12));
+    }
+
+    #[test]
+    fn parser_fields_test() {
+        assert_eq!(
+            kinds(
+                "Monkey 0:\n\
+                 Starting items: 1, x\n\
+                 Operation: new = old +\n\
+                 Test: divisible by 0\n\
+                 Test: divisible by 3\n\
+                 Test: divisible by 5\n\
+                 Throws: far\n"
+            ),
+            vec![
+                (0, 1, MonkeyErrorKind::MissingField("Starting items")),
+                (0, 1, MonkeyErrorKind::MissingField("Operation")),
+                (0, 1, MonkeyErrorKind::MissingField("If true")),
+                (0, 1, MonkeyErrorKind::MissingField("If false")),
+                (
+                    0,
+                    2,
+                    MonkeyErrorKind::InvalidValue {
+                        field: "Starting items",
+                        value: "1, x".to_owned()
+                    }
+                ),
+                (
+                    0,
+                    3,
+                    MonkeyErrorKind::InvalidOperation(ExprError::UnexpectedEnd)
+                ),
+                (0, 4, MonkeyErrorKind::ZeroDivisor),
+                (0, 6, MonkeyErrorKind::DuplicateField("Test")),
+                (0, 7, MonkeyErrorKind::UnknownField("Throws".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn parser_game_test() {
+        let errors = kinds(
+            "Monkey 0:\n\
+             Starting items: 1\n\
+             Operation: new = old\n\
+             Test: divisible by 2\n\
+             If true: throw to monkey 0\n\
+             If false: throw to monkey 2\n\
+             \n\
+             \n\
+             Monkey 3:\n\
+             Starting items: 1\n\
+             Operation: new = old\n\
+             Test: divisible by 2\n\
+             If true: throw to monkey 0\n\
+             If false: throw to monkey 0\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                (0, 5, MonkeyErrorKind::ThrowsToItself { field: "If true" }),
+                (
+                    0,
+                    6,
+                    MonkeyErrorKind::UnknownTarget {
+                        field: "If false",
+                        target: 2
+                    }
+                ),
+                (
+                    1,
+                    9,
+                    MonkeyErrorKind::HeaderOutOfOrder {
+                        expected: 1,
+                        found: 3
+                    }
+                ),
+            ]
+        );
+        let error = parse_monkeys("Ape 0:\nStarting items: 1\n").unwrap_err();
+        assert_eq!(
+            error[0].to_string(),
+            "block 0, line 1: expected a `Monkey N:` header, found `Ape 0:`"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use std::{fmt::Write, str::FromStr};
+
+use super::{play_round, rules::Rules, Integral, ItemPosition, Monkey, Relief, RoundError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("Invalid report format {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportOptions {
+    // Rounds after which the items held by each monkey are listed, 0 for the start
+    pub snapshots: Vec<usize>,
+    // Starting item to follow, as the monkey holding it and its index
+    pub item: Option<(usize, usize)>,
+}
+
+// An inspection of the followed item: its worry level before, after the operation
+// and after the relief.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JourneyStep {
+    pub round: usize,
+    pub monkey: usize,
+    pub worry: Integral,
+    pub new: Integral,
+    pub relieved: Integral,
+    pub thrown_to: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    // Inspections of each monkey during each round
+    pub inspections: Vec<Vec<u64>>,
+    pub snapshots: Vec<(usize, Vec<Vec<Integral>>)>,
+    pub journey: Option<((usize, usize), Vec<JourneyStep>)>,
+}
+
+// Items do not affect each other, so an item can be followed on its own. The item is
+// given by its index among the starting items of its monkey.
+fn follow_item(
+    monkeys: &[Monkey],
+    (mut monkey, item_index): (usize, usize),
+    rounds: usize,
+    relief: Relief,
+) -> Result<Vec<JourneyStep>, RoundError> {
+    let mut worry = monkeys[monkey].items[item_index];
+    let position = ItemPosition::Starting {
+        monkey,
+        index: item_index,
+    };
+    let mut steps = vec![];
+    let mut round = 1;
+    while round <= rounds {
+        let current = &monkeys[monkey];
+        let overflow = |error| RoundError {
+            round,
+            monkey,
+            position,
+            item: worry.to_string(),
+            error,
+        };
+        let new = current.operation.eval(&worry).map_err(overflow)?;
+        let relieved = relief.apply(new).map_err(overflow)?;
+        let thrown_to = if relieved.is_multiple_of(current.divisibility_check) {
+            current.monkey_true
+        } else {
+            current.monkey_false
+        };
+        steps.push(JourneyStep {
+            round,
+            monkey,
+            worry,
+            new,
+            relieved,
+            thrown_to,
+        });
+        if thrown_to <= monkey {
+            round += 1;
+        }
+        (monkey, worry) = (thrown_to, relieved);
+    }
+    Ok(steps)
+}
+
+fn held_items(monkeys: &[Monkey]) -> Vec<Vec<Integral>> {
+    monkeys.iter().map(|monkey| monkey.items.clone()).collect()
+}
+
+pub fn build_report(
+    monkeys: &[Monkey],
+    rules: &Rules,
+    options: &ReportOptions,
+) -> Result<Report, String> {
+    let journey = match options.item {
+        Some((monkey, index)) => {
+            if monkeys.get(monkey).is_none_or(|m| index >= m.items.len()) {
+                return Err(format!("Monkey {} has no starting item #{}", monkey, index));
+            }
+            let steps = follow_item(monkeys, (monkey, index), rules.rounds, rules.relief)
+                .map_err(|e| format!("Failed at {}", e))?;
+            Some(((monkey, index), steps))
+        }
+        None => None,
+    };
+    let mut monkeys = monkeys.to_vec();
+    let mut snapshots = vec![];
+    if options.snapshots.contains(&0) {
+        snapshots.push((0, held_items(&monkeys)));
+    }
+    let mut inspections = vec![];
+    for round in 1..=rules.rounds {
+        let before: Vec<_> = monkeys.iter().map(|m| m.inspected).collect();
+        play_round(&mut monkeys, round, rules).map_err(|e| format!("Failed at {}", e))?;
+        inspections.push(
+            monkeys
+                .iter()
+                .zip(before)
+                .map(|(m, before)| m.inspected - before)
+                .collect(),
+        );
+        if options.snapshots.contains(&round) {
+            snapshots.push((round, held_items(&monkeys)));
+        }
+    }
+    Ok(Report {
+        inspections,
+        snapshots,
+        journey,
+    })
+}
+
+fn join(items: &[Integral], separator: &str) -> String {
+    let items: Vec<_> = items.iter().map(|item| item.to_string()).collect();
+    items.join(separator)
+}
+
+impl Report {
+    // Item lists use the wording of the puzzle text.
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (i, inspected) in self.inspections.iter().enumerate() {
+            writeln!(out, "== Round {} ==", i + 1).unwrap();
+            for (monkey, count) in inspected.iter().enumerate() {
+                writeln!(out, "Monkey {} inspected items {} times.", monkey, count).unwrap();
+            }
+        }
+        for (round, items) in &self.snapshots {
+            writeln!(out, "== After round {} ==", round).unwrap();
+            for (monkey, items) in items.iter().enumerate() {
+                let line = format!("Monkey {}: {}", monkey, join(items, ", "));
+                writeln!(out, "{}", line.trim_end()).unwrap();
+            }
+        }
+        if let Some(((monkey, index), steps)) = &self.journey {
+            writeln!(out, "== Item #{} of monkey {} ==", index, monkey).unwrap();
+            for step in steps {
+                writeln!(
+                    out,
+                    "Round {}: monkey {} inspects {}, operation gives {}, relief gives {}, thrown to monkey {}",
+                    step.round, step.monkey, step.worry, step.new, step.relieved, step.thrown_to
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+
+    // One table per section, separated by blank lines.
+    fn to_csv(&self) -> String {
+        let mut tables = vec![];
+        let mut table = "round,monkey,inspections\n".to_owned();
+        for (i, inspected) in self.inspections.iter().enumerate() {
+            for (monkey, count) in inspected.iter().enumerate() {
+                writeln!(table, "{},{},{}", i + 1, monkey, count).unwrap();
+            }
+        }
+        tables.push(table);
+        if !self.snapshots.is_empty() {
+            let mut table = "round,monkey,items\n".to_owned();
+            for (round, items) in &self.snapshots {
+                for (monkey, items) in items.iter().enumerate() {
+                    writeln!(table, "{},{},{}", round, monkey, join(items, " ")).unwrap();
+                }
+            }
+            tables.push(table);
+        }
+        if let Some((_, steps)) = &self.journey {
+            let mut table = "round,monkey,worry,new,relieved,thrown_to\n".to_owned();
+            for step in steps {
+                writeln!(
+                    table,
+                    "{},{},{},{},{},{}",
+                    step.round, step.monkey, step.worry, step.new, step.relieved, step.thrown_to
+                )
+                .unwrap();
+            }
+            tables.push(table);
+        }
+        tables.join("\n")
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Text => self.to_text(),
+            ReportFormat::Csv => self.to_csv(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_11::{parser::parse_monkeys, report::*, rules::*},
+        utilities::read_file,
+    };
+
+    fn sample_report(rules: &Rules, options: &ReportOptions) -> Result<Report, String> {
+        let monkeys = parse_monkeys(&read_file("problem_11_sample")).unwrap();
+        build_report(&monkeys, rules, options)
+    }
+
+    #[test]
+    fn report_text_test() {
+        let rules = Rules::new(20, Relief::Divide(3));
+        let report = sample_report(
+            &rules,
+            &ReportOptions {
+                snapshots: vec![1],
+                item: Some((0, 0)),
+            },
+        )
+        .unwrap();
+        let totals: Vec<u64> = (0..4)
+            .map(|monkey| report.inspections.iter().map(|round| round[monkey]).sum())
+            .collect();
+        assert_eq!(totals, vec![101, 95, 7, 105]);
+        let text = report.render(ReportFormat::Text);
+        assert!(text.contains(
+            "== After round 1 ==\n\
+             Monkey 0: 20, 23, 27, 26\n\
+             Monkey 1: 2080, 25, 167, 207, 401, 1046\n\
+             Monkey 2:\n\
+             Monkey 3:\n"
+        ));
+        assert!(text.contains(
+            "== Item #0 of monkey 0 ==\n\
+             Round 1: monkey 0 inspects 79, operation gives 1501, relief gives 500, thrown to monkey 3\n\
+             Round 1: monkey 3 inspects 500, operation gives 503, relief gives 167, thrown to monkey 1\n\
+             Round 2: monkey 1 inspects 167,"
+        ));
+    }
+
+    #[test]
+    fn report_csv_test() {
+        let rules = Rules::new(1, Relief::Divide(3));
+        let report = sample_report(
+            &rules,
+            &ReportOptions {
+                snapshots: vec![0],
+                item: Some((2, 0)),
+            },
+        )
+        .unwrap();
+        let csv = report.render(ReportFormat::Csv);
+        assert!(csv.starts_with("round,monkey,inspections\n1,0,2\n1,1,4\n1,2,3\n1,3,5\n\n"));
+        assert!(csv.contains("round,monkey,items\n0,0,79 98\n"));
+        assert!(csv.ends_with("round,monkey,worry,new,relieved,thrown_to\n1,2,79,6241,2080,1\n"));
+        assert_eq!(
+            sample_report(
+                &rules,
+                &ReportOptions {
+                    snapshots: vec![],
+                    item: Some((2, 5)),
+                }
+            ),
+            Err("Monkey 2 has no starting item #5".to_owned())
+        );
+    }
+
+    #[test]
+    fn report_item_error_test() {
+        let options = ReportOptions {
+            snapshots: vec![],
+            item: Some((1, 2)),
+        };
+        let error = sample_report(&Rules::new(100, Relief::None), &options).unwrap_err();
+        assert!(error.starts_with("Failed at round "));
+        assert!(error.contains(", starting item #2 of monkey 1 with worry level "));
+    }
+}
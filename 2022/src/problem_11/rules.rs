@@ -0,0 +1,174 @@
+use std::{cmp::Reverse, str::FromStr};
+
+use super::{Integral, Relief};
+
+// Order in which a monkey inspects the items it holds. Thrown items are caught at the
+// end of the list, so LIFO inspects the last caught item first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThrowOrder {
+    #[default]
+    Fifo,
+    Lifo,
+}
+
+impl FromStr for ThrowOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(Self::Fifo),
+            "lifo" => Ok(Self::Lifo),
+            _ => Err(format!("Invalid throw order {}", s)),
+        }
+    }
+}
+
+// How the inspections of the monkeys give the monkey business
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    // Product of the k largest counts
+    TopProduct(usize),
+    Sum,
+    Max,
+}
+
+impl Default for Metric {
+    fn default() -> Self {
+        Self::TopProduct(2)
+    }
+}
+
+impl Metric {
+    pub fn apply(self, inspected: &[u64]) -> Result<u128, String> {
+        match self {
+            Self::TopProduct(k) => {
+                if k > inspected.len() {
+                    return Err(format!(
+                        "Cannot multiply the top {} of {} monkeys",
+                        k,
+                        inspected.len()
+                    ));
+                }
+                let mut inspected = inspected.to_vec();
+                inspected.sort_unstable_by_key(|&n| Reverse(n));
+                inspected[..k]
+                    .iter()
+                    .try_fold(1u128, |product, &n| product.checked_mul(n as u128))
+                    .ok_or_else(|| "The monkey business overflows".to_owned())
+            }
+            Self::Sum => Ok(inspected.iter().map(|&n| n as u128).sum()),
+            Self::Max => Ok(inspected.iter().max().map_or(0, |&n| n as u128)),
+        }
+    }
+}
+
+// `product` alone multiplies the top 2.
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid metric {}", s);
+        match s.split_once(':') {
+            Some(("product", k)) => match k.parse().map_err(|_| error())? {
+                0 => Err(error()),
+                k => Ok(Self::TopProduct(k)),
+            },
+            None if s == "product" => Ok(Self::default()),
+            None if s == "sum" => Ok(Self::Sum),
+            None if s == "max" => Ok(Self::Max),
+            _ => Err(error()),
+        }
+    }
+}
+
+// A relief as given on the command line. Without a value, the modulus is the common
+// modulus of the monkeys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReliefRule {
+    None,
+    Divide(Integral),
+    Modulo(Option<Integral>),
+}
+
+impl FromStr for ReliefRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid relief {}", s);
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => match value.parse().map_err(|_| error())? {
+                0 => return Err(error()),
+                value => (name, Some(value)),
+            },
+            None => (s, None),
+        };
+        match (name, value) {
+            ("none", None) => Ok(Self::None),
+            ("divide", Some(divisor)) => Ok(Self::Divide(divisor)),
+            ("modulo", modulus) => Ok(Self::Modulo(modulus)),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub rounds: usize,
+    pub relief: Relief,
+    pub order: ThrowOrder,
+    pub metric: Metric,
+}
+
+impl Rules {
+    // FIFO order and the product of the top 2, as in the puzzle.
+    pub fn new(rounds: usize, relief: Relief) -> Self {
+        Self {
+            rounds,
+            relief,
+            order: ThrowOrder::default(),
+            metric: Metric::default(),
+        }
+    }
+}
+
+// Changes to the rules of a part, unset ones are kept
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuleOverrides {
+    pub rounds: Option<usize>,
+    pub relief: Option<ReliefRule>,
+    pub order: Option<ThrowOrder>,
+    pub metric: Option<Metric>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_11::rules::*;
+
+    #[test]
+    fn rules_parse_test() {
+        assert_eq!("lifo".parse(), Ok(ThrowOrder::Lifo));
+        assert_eq!("product".parse(), Ok(Metric::TopProduct(2)));
+        assert_eq!("product:3".parse(), Ok(Metric::TopProduct(3)));
+        assert_eq!("max".parse(), Ok(Metric::Max));
+        assert!("product:0".parse::<Metric>().is_err());
+        assert!("sum:2".parse::<Metric>().is_err());
+        assert_eq!("none".parse(), Ok(ReliefRule::None));
+        assert_eq!("divide:3".parse(), Ok(ReliefRule::Divide(3)));
+        assert_eq!("modulo".parse(), Ok(ReliefRule::Modulo(None)));
+        assert_eq!("modulo:96577".parse(), Ok(ReliefRule::Modulo(Some(96577))));
+        assert!("divide".parse::<ReliefRule>().is_err());
+        assert!("divide:0".parse::<ReliefRule>().is_err());
+        assert!("none:1".parse::<ReliefRule>().is_err());
+    }
+
+    #[test]
+    fn rules_metric_test() {
+        let inspected = [101, 95, 7, 105];
+        assert_eq!(Metric::default().apply(&inspected), Ok(10605));
+        assert_eq!(Metric::TopProduct(3).apply(&inspected), Ok(1007475));
+        assert_eq!(Metric::Sum.apply(&inspected), Ok(308));
+        assert_eq!(Metric::Max.apply(&inspected), Ok(105));
+        assert!(Metric::TopProduct(5).apply(&inspected).is_err());
+        assert!(Metric::TopProduct(3).apply(&[u64::MAX; 3]).is_err());
+    }
+}
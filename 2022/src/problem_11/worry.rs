@@ -0,0 +1,117 @@
+use std::{fmt, str::FromStr};
+
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+
+use super::{
+    expression::{BinaryOp, EvalError},
+    Integral,
+};
+
+// A representation of worry levels. Operations report overflows and divisions by
+// zero instead of wrapping or panicking.
+pub trait Worry: Clone + fmt::Debug + fmt::Display + PartialEq + From<Integral> {
+    fn apply(&self, op: BinaryOp, rhs: &Self) -> Result<Self, EvalError>;
+
+    fn rem_integral(&self, divisor: Integral) -> Integral;
+}
+
+macro_rules! impl_primitive_worry {
+    ($($t:ty),*) => {
+        $(
+            impl Worry for $t {
+                fn apply(&self, op: BinaryOp, rhs: &Self) -> Result<Self, EvalError> {
+                    match op {
+                        BinaryOp::Add => self.checked_add(*rhs).ok_or(EvalError::Overflow),
+                        // Worry levels are unsigned, going below zero is an overflow
+                        BinaryOp::Sub => self.checked_sub(*rhs).ok_or(EvalError::Overflow),
+                        BinaryOp::Mul => self.checked_mul(*rhs).ok_or(EvalError::Overflow),
+                        BinaryOp::Div => self.checked_div(*rhs).ok_or(EvalError::DivisionByZero),
+                        BinaryOp::Rem => self.checked_rem(*rhs).ok_or(EvalError::DivisionByZero),
+                    }
+                }
+
+                fn rem_integral(&self, divisor: Integral) -> Integral {
+                    (*self % divisor as $t) as Integral
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive_worry!(u64, u128);
+
+// Exact at any size, only subtractions below zero and divisions by zero fail.
+impl Worry for BigUint {
+    fn apply(&self, op: BinaryOp, rhs: &Self) -> Result<Self, EvalError> {
+        match op {
+            BinaryOp::Add => Ok(self + rhs),
+            BinaryOp::Sub if self < rhs => Err(EvalError::Overflow),
+            BinaryOp::Sub => Ok(self - rhs),
+            BinaryOp::Mul => Ok(self * rhs),
+            _ if rhs.is_zero() => Err(EvalError::DivisionByZero),
+            BinaryOp::Div => Ok(self / rhs),
+            BinaryOp::Rem => Ok(self % rhs),
+        }
+    }
+
+    fn rem_integral(&self, divisor: Integral) -> Integral {
+        (self % divisor).to_u64().unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Representation {
+    U64,
+    U128,
+    Big,
+}
+
+impl fmt::Display for Representation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::U64 => write!(f, "u64"),
+            Self::U128 => write!(f, "u128"),
+            Self::Big => write!(f, "arbitrary precision"),
+        }
+    }
+}
+
+impl FromStr for Representation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(Self::U64),
+            "u128" => Ok(Self::U128),
+            "big" => Ok(Self::Big),
+            _ => Err(format!("Invalid precision {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_11::worry::*;
+
+    #[test]
+    fn worry_apply_test() {
+        assert_eq!(u64::MAX.apply(BinaryOp::Add, &1), Err(EvalError::Overflow));
+        assert_eq!((u64::MAX as u128).apply(BinaryOp::Add, &1), Ok(1 << 64));
+        let big = BigUint::from(u128::MAX);
+        assert_eq!(
+            big.apply(BinaryOp::Mul, &big)
+                .unwrap()
+                .rem_integral(1 << 20),
+            1
+        );
+        assert_eq!(
+            BigUint::from(3u64).apply(BinaryOp::Sub, &BigUint::from(4u64)),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(
+            BigUint::from(3u64).apply(BinaryOp::Rem, &BigUint::zero()),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+}
@@ -1,8 +1,12 @@
 #![allow(dead_code)]
 
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{cmp::Ordering, collections::BinaryHeap, io};
 
-use crate::utilities::read_file;
+use crate::{
+    image::{Image, ImageFormat, Rgb},
+    trace::trace_event,
+    utilities::read_file,
+};
 
 fn get_input() -> String {
     // read_file("problem_12_sample")
@@ -57,6 +61,7 @@ struct Node {
     category: Category,
     distance: Distance,
     position: Position,
+    previous: Option<Position>,
 }
 
 impl Ord for Node {
@@ -125,6 +130,33 @@ impl Graph {
         self.nodes[x][y]
     }
 
+    fn path_to(&self, end: Position) -> Vec<Position> {
+        let mut path = vec![end];
+        while let Some(previous) = self.get_node(*path.last().unwrap()).previous {
+            path.push(previous);
+        }
+        path.reverse();
+        path
+    }
+
+    fn to_image(&self, path: &[Position]) -> Image {
+        let rows = self.nodes.len();
+        let cols = if rows > 0 { self.nodes[0].len() } else { 0 };
+        let mut image = Image::new(rows, cols, Rgb::BLACK);
+        for node in self.nodes.iter().flatten() {
+            let level = (node.height - 'a' as i16) * 255 / ('z' as i16 - 'a' as i16);
+            image.set(node.position.x, node.position.y, Rgb::gray(level as u8));
+        }
+        for &Position { x, y } in path {
+            image.set(x, y, Rgb::RED);
+        }
+        if let (Some(&start), Some(&end)) = (path.first(), path.last()) {
+            image.set(start.x, start.y, Rgb::GREEN);
+            image.set(end.x, end.y, Rgb::BLUE);
+        }
+        image
+    }
+
     fn reacheable_node_positions<BarrierFn>(
         &self,
         pos @ Position { x, y }: Position,
@@ -171,6 +203,7 @@ fn parse_input() -> Graph {
                         category,
                         distance: Distance::Infinite,
                         position: Position { x, y },
+                        previous: None,
                     }
                 })
                 .collect()
@@ -181,12 +214,21 @@ fn parse_input() -> Graph {
         })
 }
 
+fn finite_distance(node: &Node) -> u64 {
+    match node.distance {
+        Distance::Infinite => panic!("Invalid distance"),
+        Distance::Finite(d) => d,
+    }
+}
+
+// Returns the first node satisfying `end_predicate`. The `previous` links left in
+// `graph` allow to reconstruct the path leading to it.
 fn dijkstra<EndPredicate, BarrierFn>(
-    mut graph: Graph,
+    graph: &mut Graph,
     start: Position,
     end_predicate: EndPredicate,
     barrier_function: BarrierFn,
-) -> u64
+) -> Node
 where
     EndPredicate: Fn(&Node) -> bool,
     BarrierFn: Fn(i16, i16) -> bool + Copy,
@@ -195,11 +237,24 @@ where
     unvisited.push(graph.get_node(start));
 
     while let Some(node) = unvisited.pop() {
+        trace_event!(
+            Trace,
+            "node popped",
+            x = node.position.x,
+            y = node.position.y,
+            distance = finite_distance(&node),
+            frontier = unvisited.len()
+        );
         if end_predicate(&node) {
-            return match node.distance {
-                Distance::Infinite => panic!("Invalid distance"),
-                Distance::Finite(d) => d,
-            };
+            trace_event!(
+                Debug,
+                "end reached",
+                x = node.position.x,
+                y = node.position.y,
+                distance = finite_distance(&node),
+                frontier = unvisited.len()
+            );
+            return node;
         }
         for nearest in graph.reacheable_node_positions(node.position, barrier_function) {
             match node.distance {
@@ -211,6 +266,7 @@ where
                     if tentative_dist < nearest_node.distance {
                         nearest_node.distance = tentative_dist;
                         graph.nodes[nearest.x][nearest.y].distance = tentative_dist;
+                        graph.nodes[nearest.x][nearest.y].previous = Some(node.position);
                         unvisited.push(nearest_node);
                     }
                 }
@@ -220,8 +276,7 @@ where
     panic!("End never reached");
 }
 
-fn solution_part_1() -> u64 {
-    let mut graph = parse_input();
+fn shortest_path_from_start(graph: &mut Graph) -> Node {
     let start = graph.find_start();
     graph.nodes[start.x][start.y].distance = Distance::Finite(0);
     let end = graph.find_end();
@@ -233,16 +288,29 @@ fn solution_part_1() -> u64 {
     )
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_1() -> u64 {
+    let mut graph = parse_input();
+    finite_distance(&shortest_path_from_start(&mut graph))
+}
+
+pub fn solution_part_2() -> u64 {
     let mut graph = parse_input();
     let start = graph.find_end();
     graph.nodes[start.x][start.y].distance = Distance::Finite(0);
-    dijkstra(
-        graph,
+    let end = dijkstra(
+        &mut graph,
         start,
         |node| node.height == ('a' as i16),
         |h1, h2| h1 - h2 <= 1,
-    )
+    );
+    finite_distance(&end)
+}
+
+pub fn export_image(path: &str, format: ImageFormat) -> io::Result<()> {
+    let mut graph = parse_input();
+    let end = shortest_path_from_start(&mut graph);
+    let path_to_end = graph.path_to(end.position);
+    graph.to_image(&path_to_end).save(path, format)
 }
 
 #[cfg(test)]
@@ -258,4 +326,14 @@ mod tests {
     fn problem_12_solution_part_2_test() {
         println!("problem 12 solution 2: {}", solution_part_2());
     }
+
+    #[test]
+    fn problem_12_path_to_test() {
+        let mut graph = parse_input();
+        let end = shortest_path_from_start(&mut graph);
+        let path = graph.path_to(end.position);
+        assert_eq!(path.len() as u64, finite_distance(&end) + 1);
+        assert_eq!(path[0], graph.find_start());
+        assert_eq!(*path.last().unwrap(), graph.find_end());
+    }
 }
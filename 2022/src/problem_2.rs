@@ -44,7 +44,7 @@ fn get_round_score(player_moves: &(char, char)) -> u64 {
         }
 }
 
-fn solution_part_1() -> u64 {
+pub fn solution_part_1() -> u64 {
     parse_input().iter().map(get_round_score).sum()
 }
 
@@ -83,7 +83,7 @@ fn get_round_score_v2(player_moves: (char, char)) -> u64 {
         }
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     parse_input()
         .into_iter()
         .map(tool::compose(get_round_score_v2, get_round_moves))
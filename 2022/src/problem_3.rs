@@ -44,7 +44,7 @@ fn split_line_into_vector_of_chars(line: String) -> (Vec<char>, Vec<char>) {
     (first_half, second_half)
 }
 
-fn solution_part_1() -> u64 {
+pub fn solution_part_1() -> u64 {
     parse_input()
         .into_iter()
         .map(|line| {
@@ -55,7 +55,7 @@ fn solution_part_1() -> u64 {
         .sum()
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     parse_input_2()
         .into_iter()
         .map(|group| {
@@ -35,8 +35,7 @@ fn fully_contains((rng_1, rng_2): &(Range, Range)) -> bool {
 }
 
 fn overlaps((rng_1, rng_2): &(Range, Range)) -> bool {
-    !(rng_2.min > rng_1.max || rng_2.max < rng_1.min)
-        || !(rng_1.min > rng_2.max || rng_1.max < rng_2.min)
+    rng_2.min <= rng_1.max && rng_2.max >= rng_1.min
 }
 
 fn generic_solution<F>(f: F) -> u64
@@ -46,11 +45,11 @@ where
     parse_input().into_iter().filter(f).count() as u64
 }
 
-fn solution_part_1() -> u64 {
+pub fn solution_part_1() -> u64 {
     generic_solution(fully_contains)
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     generic_solution(overlaps)
 }
 
@@ -1,17 +1,23 @@
 #![allow(dead_code)]
 
-use crate::utilities::{as_maybe_num, read_file};
+use crate::{
+    recording::Simulation,
+    trace::trace_event,
+    utilities::{as_maybe_num, read_file},
+};
 
 fn get_input() -> String {
     read_file("problem_5_sample")
 }
 
+#[derive(Clone, Copy)]
 struct Instruction {
     crates: usize,
     from: usize,
     to: usize,
 }
 
+#[derive(Clone)]
 struct Stacks {
     configuration: Vec<Vec<char>>,
 }
@@ -26,24 +32,46 @@ impl Stacks {
 
     fn execute_keep_order(&mut self, &Instruction { crates, from, to }: &Instruction) {
         let from_len = self.configuration[from].len();
-        let mut to_push: Vec<_> = self.configuration[from][from_len - crates..from_len]
-            .iter()
-            .cloned()
-            .collect();
+        let mut to_push = self.configuration[from][from_len - crates..from_len].to_vec();
         self.configuration[to].append(&mut to_push);
         self.configuration[from].truncate(from_len - crates);
     }
 
+    // Same layout as the puzzle text.
+    fn render(&self) -> String {
+        let height = self.configuration.iter().map(Vec::len).max().unwrap_or(0);
+        let mut lines: Vec<String> = (0..height)
+            .rev()
+            .map(|level| {
+                let row: Vec<_> = self
+                    .configuration
+                    .iter()
+                    .map(|stack| {
+                        stack
+                            .get(level)
+                            .map_or("   ".to_owned(), |c| format!("[{}]", c))
+                    })
+                    .collect();
+                row.join(" ").trim_end().to_owned()
+            })
+            .collect();
+        let numbers: Vec<_> = (1..=self.configuration.len())
+            .map(|n| format!(" {} ", n))
+            .collect();
+        lines.push(numbers.join(" ").trim_end().to_owned());
+        lines.join("\n")
+    }
+
     fn get_crates_order(self) -> String {
         self.configuration
             .into_iter()
-            .map(|stack| stack.last().unwrap().clone())
+            .map(|stack| *stack.last().unwrap())
             .collect()
     }
 }
 
 fn parse_stack(stack_str: &str) -> char {
-    stack_str.chars().skip(1).next().unwrap()
+    stack_str.chars().nth(1).unwrap()
 }
 
 fn parse_stacks(stacks_str: String) -> Stacks {
@@ -87,7 +115,7 @@ fn parse_instructions(s: &str) -> Vec<Instruction> {
 
 fn parse_input() -> (Stacks, Vec<Instruction>) {
     let input = get_input();
-    let mut parts = input.split_terminator("\r\n\r\n");
+    let mut parts = input.split_terminator("\n\n");
     let stacks = parse_stacks(parts.next().unwrap().to_owned());
     let instructions = parse_instructions(parts.next().unwrap());
     (stacks, instructions)
@@ -95,23 +123,67 @@ fn parse_input() -> (Stacks, Vec<Instruction>) {
 
 fn generic_solution<F>(executor: F) -> String
 where
-    F: Fn(&mut Stacks, &Instruction) -> (),
+    F: Fn(&mut Stacks, &Instruction),
 {
     let (mut stacks, instructions) = parse_input();
     for instruction in instructions {
+        trace_event!(
+            Trace,
+            "crates moved",
+            crates = instruction.crates,
+            from = instruction.from + 1,
+            to = instruction.to + 1
+        );
         executor(&mut stacks, &instruction);
     }
     stacks.get_crates_order()
 }
 
-fn solution_part_1() -> String {
+pub fn solution_part_1() -> String {
     generic_solution(|stacks, instruction| stacks.execute(instruction))
 }
 
-fn solution_part_2() -> String {
+pub fn solution_part_2() -> String {
     generic_solution(|stacks, instruction| stacks.execute_keep_order(instruction))
 }
 
+#[derive(Clone)]
+pub struct StacksSimulation {
+    stacks: Stacks,
+    instructions: Vec<Instruction>,
+    next: usize,
+    keep_order: bool,
+}
+
+impl Simulation for StacksSimulation {
+    fn step(&mut self) -> bool {
+        let Some(instruction) = self.instructions.get(self.next) else {
+            return false;
+        };
+        if self.keep_order {
+            self.stacks.execute_keep_order(instruction);
+        } else {
+            self.stacks.execute(instruction);
+        }
+        self.next += 1;
+        true
+    }
+
+    fn frame(&self) -> String {
+        self.stacks.render()
+    }
+}
+
+pub fn simulation(keep_order: bool) -> StacksSimulation {
+    let (stacks, instructions) = parse_input();
+    StacksSimulation {
+        stacks,
+        instructions,
+        next: 0,
+        keep_order,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::problem_5::*;
@@ -125,4 +197,10 @@ mod tests {
     fn problem_5_solution_part_2_test() {
         println!("problem 5 solution 2: {}", solution_part_2());
     }
+
+    #[test]
+    fn problem_5_render_test() {
+        let (stacks, _) = parse_input();
+        assert_eq!(stacks.render(), "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3");
+    }
 }
@@ -34,11 +34,11 @@ fn generic_solution<const N: usize>() -> u64 {
     panic!("Invalid input");
 }
 
-fn solution_part_1() -> u64 {
+pub fn solution_part_1() -> u64 {
     generic_solution::<4>()
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     generic_solution::<14>()
 }
 
@@ -1,12 +1,16 @@
 #![allow(dead_code)]
 
-use std::{
-    cell::RefCell,
-    collections::HashMap,
-    rc::{Rc, Weak},
-};
+mod export;
+mod filesystem;
+mod path;
+mod queries;
+mod transcript;
 
-use crate::utilities::read_file;
+use crate::{trace::trace_event, utilities::read_file};
+
+use filesystem::FileSystem;
+pub use queries::FindQuery;
+use transcript::{interpret, Severity};
 
 const TOTAL_SPACE: u64 = 70_000_000;
 const REQUIRED_SPACE: u64 = 30_000_000;
@@ -17,155 +21,95 @@ fn get_input() -> String {
     // read_file("problem_7_sample")
 }
 
-#[derive(Clone)]
-struct File {
-    name: String,
-    size: u64,
-}
-
-type Node = Rc<RefCell<Directory>>;
-type WeakNode = Weak<RefCell<Directory>>;
-
-#[derive(Clone)]
-struct Directory {
-    name: String,
-    children: Vec<Node>,
-    parent: WeakNode,
-    files: Vec<File>,
-}
-
-impl Directory {
-    fn new(name: String, parent: WeakNode) -> Self {
-        let parent_path = parent.upgrade().map(|p_dir| p_dir.borrow().name.clone());
-        Directory {
-            name: parent_path.map_or(name.clone(), |p| make_path(&p, &name)),
-            children: vec![],
-            parent,
-            files: vec![],
+// Warnings are traced, errors make the transcript unusable.
+fn build_file_system(log: &str) -> FileSystem {
+    let (fs, diagnostics) = interpret(log, ROOT_DIR_NAME);
+    let mut errors = vec![];
+    for diagnostic in diagnostics {
+        match diagnostic.kind.severity() {
+            Severity::Warning => trace_event!(Warn, "transcript", diagnostic = diagnostic),
+            Severity::Error => errors.push(diagnostic.to_string()),
         }
     }
-
-    fn get_child(&self, name: &str, path: &str) -> WeakNode {
-        let path_name = make_path(path, name);
-        Rc::downgrade(
-            self.children
-                .iter()
-                .find(|child| child.borrow().name == path_name)
-                .unwrap(),
-        )
-    }
-}
-
-fn make_path(parent: &str, children: &str) -> String {
-    format!("{}/{}", parent, children)
-}
-
-fn parse_command(curdir: WeakNode, ln: &str) -> WeakNode {
-    let cmd = ln.split_whitespace().skip(1).collect::<Vec<_>>();
-    match cmd[..] {
-        ["ls"] => curdir,
-        ["cd", ".."] => curdir.upgrade().unwrap().borrow().parent.clone(),
-        ["cd", dirname] => {
-            let cur = curdir.upgrade().unwrap();
-            let cur = cur.borrow();
-            cur.get_child(dirname, &cur.name)
-        }
-        _ => panic!("Invalid command {:?}", cmd),
+    if !errors.is_empty() {
+        panic!("Invalid transcript:\n{}", errors.join("\n"));
     }
+    fs
 }
 
-fn parse_directory(curdir: WeakNode, ln: &str) -> WeakNode {
-    let (_, name) = ln.split_once(' ').unwrap();
-    let new_dir = Directory::new(name.to_owned(), curdir.clone());
-    let dir = curdir.upgrade().unwrap();
-    let mut dir = dir.borrow_mut();
-    dir.children.push(Rc::new(RefCell::new(new_dir)));
-    curdir.clone()
-}
-
-fn parse_file(curdir: WeakNode, ln: &str) -> WeakNode {
-    let (size, name) = ln.split_once(' ').unwrap();
-    curdir.upgrade().unwrap().borrow_mut().files.push(File {
-        name: name.to_owned(),
-        size: size.parse().unwrap(),
-    });
-    curdir
-}
-
-fn parse_line(curdir: WeakNode, ln: &str) -> WeakNode {
-    if ln.starts_with('$') {
-        parse_command(curdir, ln)
-    } else if ln.starts_with("dir ") {
-        parse_directory(curdir, ln)
-    } else {
-        parse_file(curdir, ln)
+// Total size of every directory, computed in a single pass
+fn get_sizes(fs: &FileSystem) -> Vec<u64> {
+    let sizes = fs.sizes();
+    for (id, size) in sizes.iter().enumerate() {
+        trace_event!(Debug, "directory sized", path = fs.path(id), size = size);
     }
+    sizes
 }
 
-fn build_file_system(log: String) -> Node {
-    let root = Rc::new(RefCell::new(Directory::new(
-        ROOT_DIR_NAME.to_owned(),
-        Weak::new(),
-    )));
-    let mut current_directory = Rc::downgrade(&root);
-    // Skip first 'cd /'
-    for ln in log.lines().skip(1) {
-        current_directory = parse_line(current_directory, ln);
-    }
-    root
+fn small_directories_size(sizes: &[u64]) -> u64 {
+    sizes.iter().filter(|&&size| size <= 100_000).sum()
 }
 
-fn node_files_size(node: &Node) -> u64 {
-    node.borrow()
-        .files
+fn directory_to_delete_size(sizes: &[u64]) -> u64 {
+    let space_to_free = REQUIRED_SPACE - (TOTAL_SPACE - sizes[FileSystem::ROOT]);
+    *sizes
         .iter()
-        .fold(0, |acc, File { name: _, size }| *size + acc)
+        .filter(|&&size| size >= space_to_free)
+        .min()
+        .unwrap()
 }
 
-fn count_node_size(node: &Node) -> u64 {
-    let mut size = node_files_size(node);
-    for child in &node.borrow().children {
-        size += count_node_size(child);
-    }
-    size
+pub enum Query {
+    Tree,
+    Json,
+    Du { max_depth: Option<usize> },
+    Find(FindQuery),
 }
 
-fn count_nodes_sizes(node: &Node, sizes: &mut HashMap<String, u64>) {
-    let node_size = count_node_size(node);
-    sizes.insert(node.borrow().name.clone(), node_size);
-    for child in &node.borrow().children {
-        count_nodes_sizes(child, sizes);
-    }
+// Output of a query on the filesystem of a transcript, with the diagnostics of the
+// transcript. Lines with errors are skipped.
+pub fn query(transcript: &str, query: &Query) -> (String, Vec<String>) {
+    let (fs, diagnostics) = interpret(transcript, ROOT_DIR_NAME);
+    let sizes = fs.sizes();
+    let output = match query {
+        Query::Tree => queries::tree(&fs, &sizes),
+        Query::Json => export::to_json(&fs) + "\n",
+        Query::Du { max_depth } => queries::du(&fs, &sizes, *max_depth)
+            .into_iter()
+            .map(|(path, size)| format!("{}\t{}\n", size, path))
+            .collect(),
+        Query::Find(query) => queries::find(&fs, &sizes, query)
+            .into_iter()
+            .map(|path| path + "\n")
+            .collect(),
+    };
+    let diagnostics = diagnostics.iter().map(|d| d.to_string()).collect();
+    (output, diagnostics)
 }
 
-fn get_sizes() -> HashMap<String, u64> {
-    let fs = build_file_system(get_input());
-    let mut sizes = HashMap::new();
-    count_nodes_sizes(&fs, &mut sizes);
-    sizes
+// A transcript giving back the filesystem exported as JSON, apart from the name of
+// the root, which reading the transcript sets again
+pub fn transcript_from_json(json: &str) -> Result<String, String> {
+    let fs = export::from_json(json, ROOT_DIR_NAME).map_err(|e| e.to_string())?;
+    Ok(export::to_transcript(&fs))
 }
 
-fn solution_part_1() -> u64 {
-    get_sizes()
-        .iter()
-        .filter_map(|(_, &size)| if size <= 100_000 { Some(size) } else { None })
-        .sum()
+pub fn solution_part_1() -> u64 {
+    small_directories_size(&get_sizes(&build_file_system(&get_input())))
 }
 
-fn solution_part_2() -> u64 {
-    let sizes = get_sizes();
-    let space_to_free = REQUIRED_SPACE - (TOTAL_SPACE - sizes[ROOT_DIR_NAME]);
-    *sizes
-        .iter()
-        .filter(|(_, &size)| size >= space_to_free)
-        .min_by(|(_, sa), (_, sb)| sa.cmp(sb))
-        .unwrap()
-        .1
+pub fn solution_part_2() -> u64 {
+    directory_to_delete_size(&get_sizes(&build_file_system(&get_input())))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::problem_7::*;
+    use std::{
+        fmt::Write,
+        time::{Duration, Instant},
+    };
+
+    use crate::{problem_7::*, utilities::read_file};
 
     #[test]
     fn problem_7_solution_part_1_test() {
@@ -176,4 +120,88 @@ mod tests {
     fn problem_7_solution_part_2_test() {
         println!("problem 7 solution 2: {}", solution_part_2());
     }
+
+    #[test]
+    fn problem_7_sample_test() {
+        let fs = build_file_system(&read_file("problem_7_sample"));
+        let sizes = fs.sizes();
+        let e = fs
+            .child(fs.child(FileSystem::ROOT, "a").unwrap(), "e")
+            .unwrap();
+        assert_eq!(fs.path(e).to_string(), "/a/e");
+        let lookup = |from, path: &str| fs.lookup(from, &path.parse().unwrap());
+        assert_eq!(lookup(FileSystem::ROOT, "/d/../a/e"), Some(e));
+        assert_eq!(lookup(e, "../../a/./e"), Some(e));
+        assert_eq!(lookup(e, "/.."), Some(FileSystem::ROOT));
+        assert_eq!(lookup(FileSystem::ROOT, "a/f"), None);
+        assert_eq!(lookup(FileSystem::ROOT, "/x"), None);
+        assert_eq!(sizes[e], 584);
+        assert_eq!(sizes[FileSystem::ROOT], 48381165);
+        assert_eq!(small_directories_size(&sizes), 95437);
+        assert_eq!(directory_to_delete_size(&sizes), 24933642);
+    }
+
+    // A directory listing 6 files and 4 subdirectories, which share the rest of the
+    // `entries` files and directories. Returns the total size of the files.
+    fn generate(out: &mut String, entries: usize) -> u64 {
+        let files = entries.min(6);
+        let dirs = (entries - files).min(4);
+        let mut total = 0;
+        out.push_str("$ ls\n");
+        for i in 0..files {
+            let size = (entries * 7919 + i) as u64 % 200_000 + 1;
+            writeln!(out, "{} f{}", size, i).unwrap();
+            total += size;
+        }
+        for i in 0..dirs {
+            writeln!(out, "dir d{}", i).unwrap();
+        }
+        let rest = entries - files - dirs;
+        for i in 0..dirs {
+            writeln!(out, "$ cd d{}", i).unwrap();
+            total += generate(out, rest / dirs + usize::from(i < rest % dirs));
+            out.push_str("$ cd ..\n");
+        }
+        total
+    }
+
+    #[test]
+    fn problem_7_large_test() {
+        let mut transcript = "$ cd /\n".to_owned();
+        let total = generate(&mut transcript, 1_000_000);
+        // Timings only mean something with optimizations, as in `cargo test --release`.
+        // The best of a few runs leaves out the time other tests hold the CPU.
+        let runs = if cfg!(debug_assertions) { 1 } else { 3 };
+        let mut best = Duration::MAX;
+        let mut built = None;
+        for _ in 0..runs {
+            let start = Instant::now();
+            let fs = build_file_system(&transcript);
+            let sizes = fs.sizes();
+            best = best.min(start.elapsed());
+            built = Some((fs, sizes));
+        }
+        if !cfg!(debug_assertions) {
+            assert!(best < Duration::from_millis(750), "took {:?}", best);
+        }
+        let (fs, sizes) = built.unwrap();
+        let entries: usize = (0..fs.len())
+            .map(|id| fs.directory(id).files.len() + fs.directory(id).children.len())
+            .sum();
+        assert_eq!(entries, 1_000_000);
+        assert_eq!(sizes[FileSystem::ROOT], total);
+        for (id, &size) in sizes.iter().enumerate() {
+            if let Some(parent) = fs.parent(id) {
+                assert!(sizes[parent] >= size);
+            }
+        }
+        // Deep trees do not need deep recursion
+        let mut transcript = "$ cd /\n".to_owned();
+        for _ in 0..100_000 {
+            transcript.push_str("$ ls\n1 f\ndir d\n$ cd d\n");
+        }
+        let sizes = build_file_system(&transcript).sizes();
+        assert_eq!(sizes[FileSystem::ROOT], 100_000);
+        assert_eq!(sizes[100_000], 0);
+    }
 }
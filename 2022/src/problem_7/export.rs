@@ -0,0 +1,225 @@
+use std::{fmt, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    filesystem::{File, FileSystem, NodeId},
+    path::is_entry_name,
+};
+
+// A directory with its content nested, as stored in JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryTree {
+    pub name: String,
+    #[serde(default)]
+    pub directories: Vec<DirectoryTree>,
+    #[serde(default)]
+    pub files: Vec<File>,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Json(serde_json::Error),
+    DuplicateEntry { path: String, name: String },
+    InvalidEntry { path: String, name: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid JSON: {}", e),
+            Self::DuplicateEntry { path, name } => {
+                write!(f, "{} appears twice in directory {}", name, path)
+            }
+            Self::InvalidEntry { path, name } => {
+                write!(f, "invalid entry name `{}` in directory {}", name, path)
+            }
+        }
+    }
+}
+
+// Children have larger indices than their parent, so going backwards every child
+// tree is complete when its parent takes it.
+pub fn to_tree(fs: &FileSystem) -> DirectoryTree {
+    let mut trees: Vec<_> = (0..fs.len())
+        .map(|id| {
+            let dir = fs.directory(id);
+            Some(DirectoryTree {
+                name: dir.name.clone(),
+                directories: vec![],
+                files: dir.files.clone(),
+            })
+        })
+        .collect();
+    for id in (0..fs.len()).rev() {
+        let directories = fs
+            .directory(id)
+            .children
+            .iter()
+            .map(|&child| trees[child].take().unwrap())
+            .collect();
+        trees[id].as_mut().unwrap().directories = directories;
+    }
+    trees[FileSystem::ROOT].take().unwrap()
+}
+
+fn check_name(fs: &FileSystem, id: NodeId, name: &str) -> Result<(), ImportError> {
+    if is_entry_name(name) {
+        return Ok(());
+    }
+    Err(ImportError::InvalidEntry {
+        path: fs.path(id).to_string(),
+        name: name.to_owned(),
+    })
+}
+
+fn duplicate(fs: &FileSystem, id: NodeId, name: &str) -> ImportError {
+    ImportError::DuplicateEntry {
+        path: fs.path(id).to_string(),
+        name: name.to_owned(),
+    }
+}
+
+// Directories are added in the order an interpreter listing them depth first would
+// add them, so a regenerated transcript gives back the same indices. The root is
+// named `root_name` like an interpreted one: its name is not part of any path and a
+// transcript has no way to carry it, so the one in the tree is left out.
+pub fn from_tree(tree: &DirectoryTree, root_name: &str) -> Result<FileSystem, ImportError> {
+    let mut fs = FileSystem::new(root_name);
+    let mut stack = vec![(FileSystem::ROOT, tree)];
+    while let Some((id, dir)) = stack.pop() {
+        let mut children = vec![];
+        for child in &dir.directories {
+            check_name(&fs, id, &child.name)?;
+            let child = fs
+                .add_directory(id, &child.name)
+                .map_err(|_| duplicate(&fs, id, &child.name))?;
+            children.push(child);
+        }
+        for file in &dir.files {
+            check_name(&fs, id, &file.name)?;
+            fs.add_file(id, &file.name, file.size)
+                .map_err(|_| duplicate(&fs, id, &file.name))?;
+        }
+        stack.extend(children.into_iter().zip(&dir.directories).rev());
+    }
+    Ok(fs)
+}
+
+pub fn to_json(fs: &FileSystem) -> String {
+    serde_json::to_string_pretty(&to_tree(fs)).unwrap()
+}
+
+pub fn from_json(json: &str, root_name: &str) -> Result<FileSystem, ImportError> {
+    let tree = serde_json::from_str(json).map_err(ImportError::Json)?;
+    from_tree(&tree, root_name)
+}
+
+// A transcript listing every directory once, depth first, that the interpreter reads
+// back without diagnostics. Names go in as they are, since the interpreter takes the
+// rest of the line as the name. `None` on the stack stands for leaving a directory.
+pub fn to_transcript(fs: &FileSystem) -> String {
+    let mut out = "$ cd /\n".to_owned();
+    let mut stack = vec![Some(FileSystem::ROOT)];
+    while let Some(step) = stack.pop() {
+        let Some(id) = step else {
+            out.push_str("$ cd ..\n");
+            continue;
+        };
+        let dir = fs.directory(id);
+        if id != FileSystem::ROOT {
+            writeln!(out, "$ cd {}", dir.name).unwrap();
+            stack.push(None);
+        }
+        out.push_str("$ ls\n");
+        for &child in &dir.children {
+            writeln!(out, "dir {}", fs.directory(child).name).unwrap();
+        }
+        for file in &dir.files {
+            writeln!(out, "{} {}", file.size, file.name).unwrap();
+        }
+        stack.extend(dir.children.iter().rev().map(|&child| Some(child)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_7::{export::*, transcript::interpret},
+        utilities::read_file,
+    };
+
+    #[test]
+    fn export_json_test() {
+        let (fs, _) = interpret(&read_file("problem_7_sample"), "root");
+        let json = to_json(&fs);
+        assert!(json.contains("\"name\": \"h.lst\",\n"));
+        let imported = from_json(&json, "root").unwrap();
+        assert_eq!(imported, fs);
+        assert_eq!(imported.sizes()[FileSystem::ROOT], 48381165);
+        let renamed = json.replacen("\"name\": \"root\"", "\"name\": \"disk\"", 1);
+        assert_eq!(from_json(&renamed, "root").unwrap(), fs);
+        let error = from_json(
+            r#"{"name": "root", "files": [{"name": "a", "size": -1}]}"#,
+            "root",
+        );
+        assert!(matches!(error, Err(ImportError::Json(_))));
+        let json = r#"{
+            "name": "root",
+            "directories": [{"name": "a", "files": [{"name": "b", "size": 1}]}],
+            "files": [{"name": "a", "size": 2}]
+        }"#;
+        assert_eq!(
+            from_json(json, "root").unwrap_err().to_string(),
+            "a appears twice in directory /"
+        );
+        let json = r#"{
+            "name": "root",
+            "directories": [{"name": "a", "directories": [{"name": ".."}]}]
+        }"#;
+        assert_eq!(
+            from_json(json, "root").unwrap_err().to_string(),
+            "invalid entry name `..` in directory /a"
+        );
+        for name in ["", ".", "b/c", "b\\nc", "c\\r"] {
+            let json = format!(
+                r#"{{"name": "root", "files": [{{"name": "{}", "size": 1}}]}}"#,
+                name
+            );
+            assert!(matches!(
+                from_json(&json, "root"),
+                Err(ImportError::InvalidEntry { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn export_transcript_test() {
+        for input in ["problem_7_sample", "problem_7_input"] {
+            let (fs, _) = interpret(&read_file(input), "root");
+            let transcript = to_transcript(&fs);
+            let (parsed, diagnostics) = interpret(&transcript, "root");
+            assert_eq!(diagnostics, vec![]);
+            assert_eq!(to_tree(&parsed), to_tree(&fs));
+            assert_eq!(to_transcript(&parsed), transcript);
+        }
+        let (fs, _) = interpret(&read_file("problem_7_sample"), "root");
+        let transcript = to_transcript(&fs);
+        assert!(transcript.starts_with("$ cd /\n$ ls\ndir a\ndir d\n14848514 b.txt\n"));
+        // Regenerated indices are the ones of a depth first interpreter
+        assert_eq!(interpret(&transcript, "root").0, fs);
+
+        let mut fs = FileSystem::new("root");
+        let dir = fs.add_directory(FileSystem::ROOT, "my dir").unwrap();
+        let inner = fs.add_directory(dir, "x ").unwrap();
+        fs.add_file(dir, "x  ", 1).unwrap();
+        fs.add_file(inner, " a\tb", 2).unwrap();
+        fs.add_file(FileSystem::ROOT, "x ", 3).unwrap();
+        let transcript = to_transcript(&fs);
+        assert!(transcript.contains("$ cd my dir\n$ ls\ndir x \n1 x  \n$ cd x \n"));
+        let (parsed, diagnostics) = interpret(&transcript, "root");
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(parsed, fs);
+    }
+}
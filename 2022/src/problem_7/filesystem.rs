@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::path::VfsPath;
+
+// Index of a directory in the arena
+pub type NodeId = usize;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct File {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entry {
+    Directory(NodeId),
+    // Index in the files of the directory
+    File(usize),
+}
+
+// Most directories only have a few entries, which are cheaper to scan than to hash
+const SMALL_DIRECTORY: usize = 16;
+
+// Children and files by name, which a directory never has twice
+#[derive(Clone, Debug)]
+enum Entries {
+    // Entries in the order they were added, named by the directories and files
+    Small(Vec<Entry>),
+    Large(HashMap<String, Entry>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Directory {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    pub files: Vec<File>,
+    entries: Entries,
+}
+
+impl Directory {
+    fn new(name: &str, parent: Option<NodeId>) -> Self {
+        Self {
+            name: name.to_owned(),
+            parent,
+            children: vec![],
+            files: vec![],
+            entries: Entries::Small(vec![]),
+        }
+    }
+}
+
+// The entries only index the children and files, in an order that depends on how
+// they were added
+impl PartialEq for Directory {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.parent == other.parent
+            && self.children == other.children
+            && self.files == other.files
+    }
+}
+
+impl Eq for Directory {}
+
+// Directories are stored in a vector and refer to each other by index. A directory is
+// always added after its parent, so its index is larger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSystem {
+    directories: Vec<Directory>,
+}
+
+impl FileSystem {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new(root_name: &str) -> Self {
+        Self {
+            directories: vec![Directory::new(root_name, None)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.directories.len()
+    }
+
+    pub fn directory(&self, id: NodeId) -> &Directory {
+        &self.directories[id]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.directories[id].parent
+    }
+
+    fn entry_name(&self, id: NodeId, entry: Entry) -> &str {
+        match entry {
+            Entry::Directory(child) => &self.directories[child].name,
+            Entry::File(index) => &self.directories[id].files[index].name,
+        }
+    }
+
+    pub fn entry(&self, id: NodeId, name: &str) -> Option<Entry> {
+        match &self.directories[id].entries {
+            Entries::Small(entries) => entries
+                .iter()
+                .copied()
+                .find(|&entry| self.entry_name(id, entry) == name),
+            Entries::Large(entries) => entries.get(name).copied(),
+        }
+    }
+
+    // Indexes an entry, whose name must not be taken
+    fn insert_entry(&mut self, id: NodeId, name: &str, entry: Entry) {
+        let entries = match &mut self.directories[id].entries {
+            Entries::Small(entries) if entries.len() < SMALL_DIRECTORY => {
+                entries.push(entry);
+                return;
+            }
+            Entries::Small(entries) => std::mem::take(entries),
+            Entries::Large(entries) => {
+                entries.insert(name.to_owned(), entry);
+                return;
+            }
+        };
+        let mut large: HashMap<String, Entry> = entries
+            .into_iter()
+            .map(|entry| (self.entry_name(id, entry).to_owned(), entry))
+            .collect();
+        large.insert(name.to_owned(), entry);
+        self.directories[id].entries = Entries::Large(large);
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        match self.entry(id, name) {
+            Some(Entry::Directory(child)) => Some(child),
+            _ => None,
+        }
+    }
+
+    fn file_index(&self, id: NodeId, name: &str) -> Option<usize> {
+        match self.entry(id, name) {
+            Some(Entry::File(index)) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn file(&self, id: NodeId, name: &str) -> Option<&File> {
+        let index = self.file_index(id, name)?;
+        Some(&self.directories[id].files[index])
+    }
+
+    pub fn file_mut(&mut self, id: NodeId, name: &str) -> Option<&mut File> {
+        let index = self.file_index(id, name)?;
+        Some(&mut self.directories[id].files[index])
+    }
+
+    // Adds an empty directory to `parent`, unless it already has an entry with this
+    // name, which is returned instead
+    pub fn add_directory(&mut self, parent: NodeId, name: &str) -> Result<NodeId, Entry> {
+        if let Some(entry) = self.entry(parent, name) {
+            return Err(entry);
+        }
+        let id = self.directories.len();
+        self.directories.push(Directory::new(name, Some(parent)));
+        self.insert_entry(parent, name, Entry::Directory(id));
+        self.directories[parent].children.push(id);
+        Ok(id)
+    }
+
+    // Adds a file to `parent`, unless it already has an entry with this name, which is
+    // returned instead
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Result<(), Entry> {
+        if let Some(entry) = self.entry(parent, name) {
+            return Err(entry);
+        }
+        let files = &mut self.directories[parent].files;
+        let index = files.len();
+        files.push(File {
+            name: name.to_owned(),
+            size,
+        });
+        self.insert_entry(parent, name, Entry::File(index));
+        Ok(())
+    }
+
+    // The directory at `path`, relative to `from` unless it is absolute. `..` at the
+    // root stays there.
+    pub fn lookup(&self, from: NodeId, path: &VfsPath) -> Option<NodeId> {
+        let start = if path.is_absolute() { Self::ROOT } else { from };
+        path.components().try_fold(start, |id, name| match name {
+            ".." => Some(self.parent(id).unwrap_or(Self::ROOT)),
+            _ => self.child(id, name),
+        })
+    }
+
+    pub fn path(&self, id: NodeId) -> VfsPath {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.directories[current].parent {
+            names.push(self.directories[current].name.as_str());
+            current = parent;
+        }
+        names
+            .iter()
+            .rev()
+            .fold(VfsPath::root(), |path, name| path.child(name))
+    }
+
+    // Total size of every directory, by index. Children come after their parent, so
+    // going through the directories backwards visits them in post-order: each one is
+    // complete when it is added to its parent.
+    pub fn sizes(&self) -> Vec<u64> {
+        let mut sizes: Vec<u64> = self
+            .directories
+            .iter()
+            .map(|dir| dir.files.iter().map(|file| file.size).sum())
+            .collect();
+        for id in (1..self.directories.len()).rev() {
+            let parent = self.directories[id].parent.unwrap();
+            sizes[parent] += sizes[id];
+        }
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_7::filesystem::*;
+
+    // / with files of 1 and 2, /a with a file of 4, /a/b with a file of 8 and an empty
+    // /c
+    fn small() -> (FileSystem, [NodeId; 3]) {
+        let mut fs = FileSystem::new("root");
+        fs.add_file(FileSystem::ROOT, "x", 1).unwrap();
+        let a = fs.add_directory(FileSystem::ROOT, "a").unwrap();
+        let c = fs.add_directory(FileSystem::ROOT, "c").unwrap();
+        fs.add_file(FileSystem::ROOT, "y", 2).unwrap();
+        let b = fs.add_directory(a, "b").unwrap();
+        fs.add_file(a, "z", 4).unwrap();
+        fs.add_file(b, "w", 8).unwrap();
+        (fs, [a, b, c])
+    }
+
+    #[test]
+    fn filesystem_sizes_test() {
+        let (fs, [a, b, c]) = small();
+        let sizes = fs.sizes();
+        assert_eq!(sizes[FileSystem::ROOT], 15);
+        assert_eq!((sizes[a], sizes[b], sizes[c]), (12, 8, 0));
+        assert_eq!(FileSystem::new("root").sizes(), vec![0]);
+    }
+
+    #[test]
+    fn filesystem_lookup_test() {
+        let (fs, [a, b, c]) = small();
+        let lookup = |from, path: &str| fs.lookup(from, &path.parse().unwrap());
+        assert_eq!(lookup(FileSystem::ROOT, "a/b"), Some(b));
+        assert_eq!(lookup(b, "../../c"), Some(c));
+        assert_eq!(lookup(c, "/a"), Some(a));
+        assert_eq!(lookup(a, "../../.."), Some(FileSystem::ROOT));
+        // Files are not directories
+        assert_eq!(lookup(a, "z"), None);
+        assert_eq!(lookup(FileSystem::ROOT, "d"), None);
+        assert_eq!(fs.file(a, "z").map(|file| file.size), Some(4));
+        assert_eq!(fs.child(FileSystem::ROOT, "x"), None);
+    }
+
+    #[test]
+    fn filesystem_entries_test() {
+        let mut fs = FileSystem::new("root");
+        let mut children = vec![];
+        for i in 0..2 * SMALL_DIRECTORY {
+            let (d, f) = (format!("d{}", i), format!("f{}", i));
+            let child = fs.add_directory(FileSystem::ROOT, &d).unwrap();
+            children.push(child);
+            fs.add_file(FileSystem::ROOT, &f, i as u64).unwrap();
+            // Taken names give the entry holding them, before and after the switch to a
+            // map, and add nothing
+            assert_eq!(
+                fs.add_file(FileSystem::ROOT, &d, 0),
+                Err(Entry::Directory(child))
+            );
+            assert_eq!(fs.add_directory(FileSystem::ROOT, &f), Err(Entry::File(i)));
+            assert_eq!(
+                fs.add_directory(FileSystem::ROOT, &d),
+                Err(Entry::Directory(child))
+            );
+            for (j, &child) in children.iter().enumerate() {
+                assert_eq!(fs.child(FileSystem::ROOT, &format!("d{}", j)), Some(child));
+                assert_eq!(fs.child(FileSystem::ROOT, &format!("f{}", j)), None);
+                let file = fs.file(FileSystem::ROOT, &format!("f{}", j));
+                assert_eq!(file.map(|file| file.size), Some(j as u64));
+            }
+        }
+        assert_eq!(fs.child(FileSystem::ROOT, "x"), None);
+        // No orphan is left in the arena or the lists of the root
+        let root = fs.directory(FileSystem::ROOT);
+        assert_eq!(fs.len(), 1 + 2 * SMALL_DIRECTORY);
+        assert_eq!(root.children, children);
+        assert_eq!(root.files.len(), 2 * SMALL_DIRECTORY);
+        let total = (0..2 * SMALL_DIRECTORY as u64).sum::<u64>();
+        assert_eq!(fs.sizes()[FileSystem::ROOT], total);
+    }
+
+    #[test]
+    fn filesystem_path_test() {
+        let (fs, [a, b, c]) = small();
+        let path = |id| fs.path(id).to_string();
+        assert_eq!(path(FileSystem::ROOT), "/");
+        assert_eq!(
+            (path(a), path(b), path(c)),
+            ("/a".into(), "/a/b".into(), "/c".into())
+        );
+        // The root name is not part of paths
+        assert_eq!(fs.directory(FileSystem::ROOT).name, "root");
+    }
+}
@@ -0,0 +1,166 @@
+use std::{fmt, str::FromStr};
+
+// A normalized path: no empty or `.` components, and `..` only at the start of a
+// relative path. `..` at the root stays at the root.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VfsPath {
+    absolute: bool,
+    components: Vec<String>,
+}
+
+impl VfsPath {
+    pub fn root() -> Self {
+        Self {
+            absolute: true,
+            components: vec![],
+        }
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.components.iter().map(String::as_str)
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.components
+            .last()
+            .map(String::as_str)
+            .filter(|&name| name != "..")
+    }
+
+    fn push(&mut self, component: &str) {
+        match component {
+            "" | "." => (),
+            ".." => match self.components.last().map(String::as_str) {
+                Some(last) if last != ".." => {
+                    self.components.pop();
+                }
+                _ if self.absolute => (),
+                _ => self.components.push("..".to_owned()),
+            },
+            name => self.components.push(name.to_owned()),
+        }
+    }
+
+    // The path of an entry named `name` in this directory
+    pub fn child(&self, name: &str) -> Self {
+        let mut path = self.clone();
+        path.push(name);
+        path
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        let mut path = self.clone();
+        match path.components.last().map(String::as_str) {
+            None if self.absolute => None,
+            None | Some("..") => {
+                path.components.push("..".to_owned());
+                Some(path)
+            }
+            Some(_) => {
+                path.components.pop();
+                Some(path)
+            }
+        }
+    }
+
+    // `other` relative to this path, or `other` itself when it is absolute
+    pub fn join(&self, other: &VfsPath) -> Self {
+        if other.absolute {
+            return other.clone();
+        }
+        let mut path = self.clone();
+        for component in &other.components {
+            path.push(component);
+        }
+        path
+    }
+}
+
+// Whether `name` can name an entry, which is a single component of its path.
+// Transcripts are line based and keep the rest of the line as the name, so any
+// name without a line break is read back as written.
+pub fn is_entry_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..") && !name.contains(['/', '\n', '\r'])
+}
+
+impl FromStr for VfsPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("Empty path".to_owned());
+        }
+        let mut path = Self {
+            absolute: s.starts_with('/'),
+            components: vec![],
+        };
+        for component in s.split('/') {
+            path.push(component);
+        }
+        Ok(path)
+    }
+}
+
+impl fmt::Display for VfsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.absolute, self.components.is_empty()) {
+            (true, _) => write!(f, "/{}", self.components.join("/")),
+            (false, true) => write!(f, "."),
+            (false, false) => write!(f, "{}", self.components.join("/")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem_7::path::*;
+
+    fn path(s: &str) -> VfsPath {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn path_normalize_test() {
+        let normalized = |s: &str| path(s).to_string();
+        assert_eq!(normalized("/a/b/../c"), "/a/c");
+        assert_eq!(normalized("/a//./b/"), "/a/b");
+        assert_eq!(normalized("/../a"), "/a");
+        assert_eq!(normalized("/"), "/");
+        assert_eq!(normalized("../a/../../b"), "../../b");
+        assert_eq!(normalized("a/.."), ".");
+        assert!(path("/").is_absolute() && !path("a").is_absolute());
+        assert!("".parse::<VfsPath>().is_err());
+        assert_eq!(
+            path("/a/b").components().collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn path_join_test() {
+        let base = path("/a/b");
+        assert_eq!(base.join(&path("../c/./d")), path("/a/c/d"));
+        assert_eq!(base.join(&path("/x")), path("/x"));
+        assert_eq!(base.join(&path("../../..")), VfsPath::root());
+        assert_eq!(path("a").join(&path("../..")), path(".."));
+        assert_eq!(base.child("c"), path("/a/b/c"));
+        assert_eq!(base.parent(), Some(path("/a")));
+        assert_eq!(VfsPath::root().parent(), None);
+        assert_eq!(path("..").parent(), Some(path("../..")));
+        assert_eq!(base.file_name(), Some("b"));
+        assert_eq!(path("..").file_name(), None);
+    }
+
+    #[test]
+    fn path_entry_name_test() {
+        assert!(is_entry_name("a.txt") && is_entry_name("..a"));
+        assert!(!is_entry_name("") && !is_entry_name(".") && !is_entry_name(".."));
+        assert!(!is_entry_name("a/b") && !is_entry_name("/"));
+        assert!(is_entry_name(" a b\t") && is_entry_name(" "));
+        assert!(!is_entry_name("a\nb") && !is_entry_name("a\r"));
+    }
+}
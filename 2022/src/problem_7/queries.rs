@@ -0,0 +1,265 @@
+use std::{cmp::Reverse, fmt::Write, str::FromStr};
+
+use super::{
+    filesystem::{FileSystem, NodeId},
+    path::VfsPath,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+impl FromStr for EntryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f" => Ok(Self::File),
+            "d" => Ok(Self::Directory),
+            _ => Err(format!("Invalid entry type {}", s)),
+        }
+    }
+}
+
+// Conditions on the entries to find, unset ones match everything. The size of a
+// directory is its total size.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FindQuery {
+    pub name: Option<String>,
+    pub kind: Option<EntryKind>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+}
+
+impl FindQuery {
+    fn matches(&self, name: &str, kind: EntryKind, size: u64) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern, name))
+            && self.kind.is_none_or(|k| k == kind)
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+// `*` matches any sequence of characters and `?` any single character. On a mismatch
+// after a `*`, the star is retried one character further.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<_>, Vec<_>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    (p, n) = (star_p + 1, star_n + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Absolute paths of the directories, by index. Parents come before their children.
+fn paths(fs: &FileSystem) -> Vec<VfsPath> {
+    let mut paths = Vec::with_capacity(fs.len());
+    paths.push(VfsPath::root());
+    for id in 1..fs.len() {
+        let dir = fs.directory(id);
+        let path = paths[dir.parent.unwrap()].child(&dir.name);
+        paths.push(path);
+    }
+    paths
+}
+
+fn depths(fs: &FileSystem) -> Vec<usize> {
+    let mut depths = vec![0; fs.len()];
+    for id in 1..fs.len() {
+        depths[id] = depths[fs.parent(id).unwrap()] + 1;
+    }
+    depths
+}
+
+enum Entry {
+    Directory(NodeId),
+    File(u64),
+}
+
+// Entries of a directory in reverse order of name, so that popping gives them in
+// order.
+fn reversed_entries(fs: &FileSystem, id: NodeId) -> Vec<(&str, Entry)> {
+    let dir = fs.directory(id);
+    let mut entries: Vec<_> = dir
+        .children
+        .iter()
+        .map(|&child| (fs.directory(child).name.as_str(), Entry::Directory(child)))
+        .chain(
+            dir.files
+                .iter()
+                .map(|file| (file.name.as_str(), Entry::File(file.size))),
+        )
+        .collect();
+    entries.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    entries
+}
+
+// The filesystem drawn as by `tree`, with the size of every entry. Uses a stack of
+// pending entries per level rather than recursion, so any depth works.
+pub fn tree(fs: &FileSystem, sizes: &[u64]) -> String {
+    let mut out = format!("/ ({})\n", sizes[FileSystem::ROOT]);
+    let mut stack = vec![(String::new(), reversed_entries(fs, FileSystem::ROOT))];
+    while let Some((prefix, entries)) = stack.last_mut() {
+        let Some((name, entry)) = entries.pop() else {
+            stack.pop();
+            continue;
+        };
+        let (connector, indent) = if entries.is_empty() {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        let size = match entry {
+            Entry::File(size) => size,
+            Entry::Directory(id) => sizes[id],
+        };
+        writeln!(out, "{}{}{} ({})", prefix, connector, name, size).unwrap();
+        if let Entry::Directory(id) = entry {
+            let prefix = format!("{}{}", prefix, indent);
+            stack.push((prefix, reversed_entries(fs, id)));
+        }
+    }
+    out
+}
+
+// Directories at most `max_depth` levels below the root with their total size, the
+// largest first.
+pub fn du(fs: &FileSystem, sizes: &[u64], max_depth: Option<usize>) -> Vec<(String, u64)> {
+    let depths = depths(fs);
+    let mut entries: Vec<_> = paths(fs)
+        .into_iter()
+        .zip(sizes.iter().copied())
+        .zip(depths)
+        .filter(|(_, depth)| max_depth.is_none_or(|max| *depth <= max))
+        .map(|((path, size), _)| (path.to_string(), size))
+        .collect();
+    entries.sort_unstable_by(|(pa, sa), (pb, sb)| (Reverse(sa), pa).cmp(&(Reverse(sb), pb)));
+    entries
+}
+
+// Paths of the matching files and directories, in alphabetical order.
+pub fn find(fs: &FileSystem, sizes: &[u64], query: &FindQuery) -> Vec<String> {
+    let paths = paths(fs);
+    let mut found = vec![];
+    for (id, path) in paths.iter().enumerate() {
+        let dir = fs.directory(id);
+        let name = if id == FileSystem::ROOT {
+            "/"
+        } else {
+            &dir.name
+        };
+        if query.matches(name, EntryKind::Directory, sizes[id]) {
+            found.push(path.to_string());
+        }
+        for file in &dir.files {
+            if query.matches(&file.name, EntryKind::File, file.size) {
+                found.push(path.child(&file.name).to_string());
+            }
+        }
+    }
+    found.sort_unstable();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        problem_7::{queries::*, transcript::interpret},
+        utilities::read_file,
+    };
+
+    fn sample() -> (FileSystem, Vec<u64>) {
+        let (fs, _) = interpret(&read_file("problem_7_sample"), "root");
+        let sizes = fs.sizes();
+        (fs, sizes)
+    }
+
+    #[test]
+    fn queries_tree_test() {
+        let (fs, sizes) = sample();
+        assert_eq!(
+            tree(&fs, &sizes),
+            "\
+/ (48381165)
+├── a (94853)
+│   ├── e (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+"
+        );
+    }
+
+    #[test]
+    fn queries_du_test() {
+        let (fs, sizes) = sample();
+        let entry = |path: &str, size| (path.to_owned(), size);
+        assert_eq!(
+            du(&fs, &sizes, Some(1)),
+            vec![
+                entry("/", 48381165),
+                entry("/d", 24933642),
+                entry("/a", 94853)
+            ]
+        );
+        assert_eq!(du(&fs, &sizes, None).last(), Some(&entry("/a/e", 584)));
+    }
+
+    #[test]
+    fn queries_find_test() {
+        let (fs, sizes) = sample();
+        let query = FindQuery {
+            name: Some("*.*".to_owned()),
+            ..FindQuery::default()
+        };
+        assert_eq!(
+            find(&fs, &sizes, &query),
+            vec!["/a/h.lst", "/b.txt", "/c.dat", "/d/d.ext", "/d/d.log"]
+        );
+        let query = FindQuery {
+            kind: Some(EntryKind::Directory),
+            max_size: Some(100_000),
+            ..FindQuery::default()
+        };
+        assert_eq!(find(&fs, &sizes, &query), vec!["/a", "/a/e"]);
+        let query = FindQuery {
+            name: Some("?".to_owned()),
+            kind: Some(EntryKind::File),
+            min_size: Some(5_000),
+            ..FindQuery::default()
+        };
+        assert_eq!(find(&fs, &sizes, &query), vec!["/a/f", "/d/j", "/d/k"]);
+        assert!(glob_match("*a*b?", "xxaxbbc"));
+        assert!(!glob_match("*a*b?", "xxaxb"));
+        assert!(glob_match("**", ""));
+    }
+}
@@ -0,0 +1,527 @@
+use std::{collections::HashSet, fmt, slice};
+
+use crate::trace::trace_event;
+
+use super::{
+    filesystem::{Entry, FileSystem, NodeId},
+    path::{is_entry_name, VfsPath},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    UnknownCommand(String),
+    InvalidEntry(String),
+    CdAboveRoot,
+    NotADirectory(String),
+    // A directory that is entered but was not in the listing of its parent
+    UnlistedDirectory(String),
+    OutputWithoutLs,
+    DuplicateEntry(String),
+    // A name listed both as a file and as a directory
+    ConflictingEntry(String),
+    SizeChanged { name: String, old: u64, new: u64 },
+    // An entry known from an earlier listing or a `cd` that is not listed
+    MissingFromListing(String),
+}
+
+impl DiagnosticKind {
+    // Errors are lines that are ignored, warnings lines whose effect may not be the
+    // intended one.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::UnknownCommand(_)
+            | Self::InvalidEntry(_)
+            | Self::NotADirectory(_)
+            | Self::ConflictingEntry(_) => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownCommand(command) => write!(f, "unknown command `{}`", command),
+            Self::InvalidEntry(entry) => write!(f, "invalid listing entry `{}`", entry),
+            Self::CdAboveRoot => write!(f, "`cd ..` at the root, staying there"),
+            Self::NotADirectory(name) => write!(f, "cannot cd into file {}", name),
+            Self::UnlistedDirectory(name) => {
+                write!(f, "directory {} was not in the listing", name)
+            }
+            Self::OutputWithoutLs => write!(f, "listing entry without an `ls`"),
+            Self::DuplicateEntry(name) => write!(f, "{} listed twice", name),
+            Self::ConflictingEntry(name) => {
+                write!(f, "{} listed both as a file and as a directory", name)
+            }
+            Self::SizeChanged { name, old, new } => {
+                write!(f, "size of {} changed from {} to {}", name, old, new)
+            }
+            Self::MissingFromListing(name) => write!(f, "{} missing from the listing", name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.kind.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "line {}: {}: {}", self.line, severity, self.kind)
+    }
+}
+
+// Output of an `ls`: the directory, the line of the command, what the directory held
+// before and the names listed so far among them. Entries added by the listing are
+// told apart by their index, so a first listing needs no set of names.
+struct Listing<'a> {
+    dir: NodeId,
+    line: usize,
+    // Directories from this index on were added by the listing
+    first_new_dir: NodeId,
+    known_files: usize,
+    known_entries: usize,
+    listed_known: HashSet<&'a str>,
+}
+
+struct Interpreter<'a> {
+    fs: FileSystem,
+    cwd: NodeId,
+    // Whether the content of each directory was listed at least once, by index
+    listed: Vec<bool>,
+    listing: Option<Listing<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn report(&mut self, line: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { line, kind });
+    }
+
+    fn end_listing(&mut self) {
+        let Some(listing) = self.listing.take() else {
+            return;
+        };
+        if listing.listed_known.len() < listing.known_entries {
+            self.report_missing(&listing);
+        }
+        if self.listed.len() <= listing.dir {
+            self.listed.resize(listing.dir + 1, false);
+        }
+        self.listed[listing.dir] = true;
+    }
+
+    fn report_missing(&mut self, listing: &Listing) {
+        let dir = self.fs.directory(listing.dir);
+        let known = dir
+            .children
+            .iter()
+            .filter(|&&child| child < listing.first_new_dir)
+            .map(|&child| &self.fs.directory(child).name)
+            .chain(
+                dir.files[..listing.known_files]
+                    .iter()
+                    .map(|file| &file.name),
+            );
+        let missing: Vec<_> = known
+            .filter(|name| !listing.listed_known.contains(name.as_str()))
+            .cloned()
+            .collect();
+        for name in missing {
+            self.report(listing.line, DiagnosticKind::MissingFromListing(name));
+        }
+    }
+
+    fn is_listed(&self, dir: NodeId) -> bool {
+        self.listed.get(dir).copied().unwrap_or(false)
+    }
+
+    // Resolves the normalized path as far as it exists, then creates the directories
+    // that were never listed. Nothing changes when a component is a file.
+    fn cd(&mut self, line: usize, target: &str) {
+        // Most targets are a single name, which is already normalized
+        let path;
+        let components: Vec<_>;
+        let (mut dir, names) = if target != "." && !target.contains('/') {
+            (self.cwd, slice::from_ref(&target))
+        } else {
+            let Ok(parsed) = target.parse::<VfsPath>() else {
+                return self.report(
+                    line,
+                    DiagnosticKind::UnknownCommand(format!("cd {}", target)),
+                );
+            };
+            path = parsed;
+            components = path.components().collect();
+            let start = if path.is_absolute() {
+                FileSystem::ROOT
+            } else {
+                self.cwd
+            };
+            (start, &components[..])
+        };
+        let mut above_root = false;
+        let mut resolved = 0;
+        for &name in names {
+            dir = match name {
+                ".." => self.fs.parent(dir).unwrap_or_else(|| {
+                    above_root = true;
+                    dir
+                }),
+                _ => match self.fs.entry(dir, name) {
+                    Some(Entry::Directory(child)) => child,
+                    Some(Entry::File(_)) => {
+                        return self.report(line, DiagnosticKind::NotADirectory(name.to_owned()));
+                    }
+                    None => break,
+                },
+            };
+            resolved += 1;
+        }
+        // `..` only starts a normalized path, so the rest are names of new directories
+        if let Some(&name) = names.get(resolved) {
+            if self.is_listed(dir) {
+                self.report(line, DiagnosticKind::UnlistedDirectory(name.to_owned()));
+            }
+            for &name in &names[resolved..] {
+                dir = self.fs.add_directory(dir, name).unwrap();
+            }
+        }
+        if above_root {
+            self.report(line, DiagnosticKind::CdAboveRoot);
+        }
+        self.cwd = dir;
+    }
+
+    fn command(&mut self, line: usize, command: &'a str) {
+        trace_event!(Debug, "command", line = line, command = command);
+        self.end_listing();
+        // The target of `cd` is the rest of the line, as names can hold spaces
+        match command.strip_prefix("cd ") {
+            None if command.trim_end() == "ls" => {
+                let dir = self.fs.directory(self.cwd);
+                self.listing = Some(Listing {
+                    dir: self.cwd,
+                    line,
+                    first_new_dir: self.fs.len(),
+                    known_files: dir.files.len(),
+                    known_entries: dir.children.len() + dir.files.len(),
+                    listed_known: HashSet::new(),
+                })
+            }
+            Some(target) => self.cd(line, target),
+            None => self.report(line, DiagnosticKind::UnknownCommand(command.to_owned())),
+        }
+    }
+
+    fn entry(&mut self, line: usize, ln: &'a str) {
+        let Some((kind, name)) = ln.split_once(' ') else {
+            return self.report(line, DiagnosticKind::InvalidEntry(ln.to_owned()));
+        };
+        let size = match kind {
+            "dir" => None,
+            size => match size.parse::<u64>() {
+                Ok(size) => Some(size),
+                Err(_) => return self.report(line, DiagnosticKind::InvalidEntry(ln.to_owned())),
+            },
+        };
+        // A name that is not a single path component could never be looked up
+        if !is_entry_name(name) {
+            return self.report(line, DiagnosticKind::InvalidEntry(ln.to_owned()));
+        }
+        let dir = self.cwd;
+        // Only names that are already known need more than one lookup
+        let existing = self.fs.entry(dir, name);
+        let conflict = matches!(
+            (size, existing),
+            (None, Some(Entry::File(_))) | (Some(_), Some(Entry::Directory(_)))
+        );
+        if conflict {
+            return self.report(line, DiagnosticKind::ConflictingEntry(name.to_owned()));
+        }
+        match &mut self.listing {
+            Some(listing) => {
+                let added = match existing {
+                    Some(Entry::Directory(child)) => child >= listing.first_new_dir,
+                    Some(Entry::File(index)) => index >= listing.known_files,
+                    None => false,
+                };
+                if added || existing.is_some() && !listing.listed_known.insert(name) {
+                    self.report(line, DiagnosticKind::DuplicateEntry(name.to_owned()));
+                }
+            }
+            None => self.report(line, DiagnosticKind::OutputWithoutLs),
+        }
+        match (size, existing) {
+            (None, None) => {
+                let id = self.fs.add_directory(dir, name).unwrap();
+                trace_event!(Trace, "directory listed", path = self.fs.path(id));
+            }
+            (None, _) => (),
+            (Some(size), None) => {
+                trace_event!(Trace, "file listed", name = name, size = size);
+                self.fs.add_file(dir, name, size).unwrap();
+            }
+            (Some(size), _) => {
+                trace_event!(Trace, "file listed", name = name, size = size);
+                let file = self.fs.file_mut(dir, name).unwrap();
+                if file.size != size {
+                    let old = std::mem::replace(&mut file.size, size);
+                    let name = name.to_owned();
+                    self.report(
+                        line,
+                        DiagnosticKind::SizeChanged {
+                            name,
+                            old,
+                            new: size,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Replays the commands of a terminal transcript, starting at the root. Lines that
+// cannot be interpreted are reported and skipped, diagnostics are in line order.
+pub fn interpret(transcript: &str, root_name: &str) -> (FileSystem, Vec<Diagnostic>) {
+    let mut interpreter = Interpreter {
+        fs: FileSystem::new(root_name),
+        cwd: FileSystem::ROOT,
+        listed: vec![],
+        listing: None,
+        diagnostics: vec![],
+    };
+    // Lines are only trimmed before their first word, so names keep their spaces
+    for (i, ln) in transcript.lines().enumerate() {
+        if ln.trim().is_empty() {
+            continue;
+        }
+        let ln = ln.trim_start();
+        match ln.strip_prefix('$') {
+            Some(command) => interpreter.command(i + 1, command.trim_start()),
+            None => interpreter.entry(i + 1, ln),
+        }
+    }
+    interpreter.end_listing();
+    interpreter.diagnostics.sort_by_key(|d| d.line);
+    (interpreter.fs, interpreter.diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{problem_7::transcript::*, utilities::read_file};
+
+    #[test]
+    fn transcript_sample_test() {
+        let (fs, diagnostics) = interpret(&read_file("problem_7_sample"), "root");
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(fs.len(), 4);
+        assert_eq!(fs.sizes()[FileSystem::ROOT], 48381165);
+    }
+
+    #[test]
+    fn transcript_navigation_test() {
+        let transcript = "\
+$ cd ..
+$ ls
+dir a
+10 b
+$ cd a
+$ cd /
+$ cd c
+$ ls
+5 d
+$ cd /
+$ cd b
+$ cd x
+$ ls
+3 e
+$ pwd
+$ cd
+";
+        let (fs, diagnostics) = interpret(transcript, "root");
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, DiagnosticKind::CdAboveRoot),
+                (7, DiagnosticKind::UnlistedDirectory("c".to_owned())),
+                (11, DiagnosticKind::NotADirectory("b".to_owned())),
+                (12, DiagnosticKind::UnlistedDirectory("x".to_owned())),
+                (15, DiagnosticKind::UnknownCommand("pwd".to_owned())),
+                (16, DiagnosticKind::UnknownCommand("cd".to_owned())),
+            ]
+        );
+        assert_eq!(
+            diagnostics[2].to_string(),
+            "line 11: error: cannot cd into file b"
+        );
+        // x is created in the root, as b is not a directory
+        let x = fs.child(FileSystem::ROOT, "x").unwrap();
+        assert_eq!(fs.path(x).to_string(), "/x");
+        assert_eq!(fs.sizes()[FileSystem::ROOT], 18);
+    }
+
+    #[test]
+    fn transcript_cd_path_test() {
+        let transcript = "\
+$ cd /a/b/../c
+$ ls
+1 f
+$ cd ../../a/./c/
+$ ls
+1 f
+$ cd /a/c/f/x
+$ cd ../../../..
+";
+        let (fs, diagnostics) = interpret(transcript, "root");
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (7, DiagnosticKind::NotADirectory("f".to_owned())),
+                (8, DiagnosticKind::CdAboveRoot),
+            ]
+        );
+        // b is never entered and the cd through f leaves nothing behind
+        assert_eq!(fs.len(), 3);
+        let a = fs.child(FileSystem::ROOT, "a").unwrap();
+        assert_eq!(fs.directory(a).children.len(), 1);
+        let c = fs
+            .lookup(FileSystem::ROOT, &"/a/c".parse().unwrap())
+            .unwrap();
+        assert_eq!(fs.directory(c).files.len(), 1);
+    }
+
+    #[test]
+    fn transcript_entry_name_test() {
+        let transcript = "\
+$ ls
+dir .
+1 ..
+dir a/b
+dir \n\
+2 c
+";
+        let (fs, diagnostics) = interpret(transcript, "root");
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        let invalid = |entry: &str| DiagnosticKind::InvalidEntry(entry.to_owned());
+        assert_eq!(
+            kinds,
+            vec![
+                (2, invalid("dir .")),
+                (3, invalid("1 ..")),
+                (4, invalid("dir a/b")),
+                (5, invalid("dir ")),
+            ]
+        );
+        assert_eq!(fs.len(), 1);
+        assert_eq!(fs.directory(FileSystem::ROOT).files.len(), 1);
+    }
+
+    #[test]
+    fn transcript_spaces_test() {
+        let transcript = "\
+$ cd /
+$ ls
+dir my dir
+10 x \n\
+$ cd my dir
+$ ls
+5  lead
+$ cd /my dir/
+$ ls  \n\
+5  lead
+$ cd  my dir
+";
+        let (fs, diagnostics) = interpret(transcript, "root");
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        // ` my dir` is another name, which was not listed
+        assert_eq!(
+            kinds,
+            vec![(11, DiagnosticKind::UnlistedDirectory(" my dir".to_owned()))]
+        );
+        let dir = fs.child(FileSystem::ROOT, "my dir").unwrap();
+        assert_eq!(fs.file(FileSystem::ROOT, "x ").map(|f| f.size), Some(10));
+        assert_eq!(fs.file(dir, " lead").map(|f| f.size), Some(5));
+        assert_eq!(fs.directory(dir).files.len(), 1);
+        assert!(fs.child(dir, " my dir").is_some());
+    }
+
+    #[test]
+    fn transcript_listing_test() {
+        let transcript = "\
+$ ls
+dir a
+10 b
+20 c
+$ ls
+dir a
+10 b
+10 b
+30 c
+dir b
+$ cd a
+$ ls
+$ cd ..
+15 d
+1O e
+$ ls
+10 b
+";
+        let (fs, diagnostics) = interpret(transcript, "root");
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        let name = |name: &str| name.to_owned();
+        assert_eq!(
+            kinds,
+            vec![
+                (8, DiagnosticKind::DuplicateEntry(name("b"))),
+                (
+                    9,
+                    DiagnosticKind::SizeChanged {
+                        name: name("c"),
+                        old: 20,
+                        new: 30
+                    }
+                ),
+                (10, DiagnosticKind::ConflictingEntry(name("b"))),
+                (14, DiagnosticKind::OutputWithoutLs),
+                (15, DiagnosticKind::InvalidEntry(name("1O e"))),
+                (16, DiagnosticKind::MissingFromListing(name("a"))),
+                (16, DiagnosticKind::MissingFromListing(name("c"))),
+                (16, DiagnosticKind::MissingFromListing(name("d"))),
+            ]
+        );
+        // Re-listing does not add files again
+        assert_eq!(fs.sizes()[FileSystem::ROOT], 55);
+        assert_eq!(fs.directory(FileSystem::ROOT).files.len(), 3);
+    }
+}
@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 
-use crate::utilities::{as_num, read_file};
+use std::io;
+
+use crate::{
+    image::{Image, ImageFormat, Rgb},
+    utilities::{as_num, read_file},
+};
 
 fn get_input() -> String {
     read_file("problem_8_input")
@@ -35,7 +40,7 @@ struct Field {
 }
 
 impl Field {
-    fn iter(&self) -> FieldIter {
+    fn iter(&self) -> FieldIter<'_> {
         FieldIter::new(&self.tree_field)
     }
 
@@ -74,6 +79,15 @@ impl Field {
         row.all(|x| x < h)
     }
 
+    fn to_image(&self) -> Image {
+        let (rows, cols) = field_size(&self.tree_field);
+        let mut image = Image::new(rows, cols, Rgb::BLACK);
+        for Tree { x, y, h } in self.iter() {
+            image.set(x, y, height_color(h));
+        }
+        image
+    }
+
     fn line_score(h: Height, row: impl Iterator<Item = Height>) -> u64 {
         let mut score = 0;
         for tree_height in row {
@@ -124,20 +138,24 @@ impl<'a> Iterator for FieldIter<'a> {
     }
 }
 
+fn height_color(h: Height) -> Rgb {
+    Rgb(0, 30 + h * 25, 0)
+}
+
 fn parse_input() -> Field {
     let tree_field = get_input().lines().fold(vec![], |mut acc, ln| {
-        acc.push(ln.chars().map(|c| as_num::<Height>(c)).collect());
+        acc.push(ln.chars().map(as_num::<Height>).collect());
         acc
     });
     Field { tree_field }
 }
 
-fn solution_part_1() -> usize {
+pub fn solution_part_1() -> usize {
     let field = parse_input();
     field.iter().filter(|&tree| field.is_visible(tree)).count()
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     let field = parse_input();
     field
         .iter()
@@ -146,6 +164,10 @@ fn solution_part_2() -> u64 {
         .unwrap()
 }
 
+pub fn export_image(path: &str, format: ImageFormat) -> io::Result<()> {
+    parse_input().to_image().save(path, format)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::problem_8::*;
@@ -159,4 +181,12 @@ mod tests {
     fn problem_8_solution_part_2_test() {
         println!("problem 8 solution 2: {}", solution_part_2());
     }
+
+    #[test]
+    fn problem_8_to_image_test() {
+        let field = parse_input();
+        let image = field.to_image();
+        let tree = field.iter().nth(7).unwrap();
+        assert_eq!(image.get(tree.x, tree.y), height_color(tree.h));
+    }
 }
@@ -1,8 +1,13 @@
 #![allow(dead_code)]
 
-use std::collections::HashSet;
+use std::{collections::HashSet, io};
 
-use crate::utilities::read_file;
+use crate::{
+    image::{Image, ImageFormat, Rgb},
+    recording::Simulation,
+    trace::trace_event,
+    utilities::read_file,
+};
 
 fn get_input() -> String {
     // read_file("problem_9_sample_2")
@@ -30,6 +35,7 @@ struct Position(i64, i64);
 
 type Positions = HashSet<Position>;
 
+#[derive(Clone)]
 struct Head {
     position: Position,
 }
@@ -50,6 +56,7 @@ struct Knot {
     position: Position,
 }
 
+#[derive(Clone)]
 struct Rope<const KNOTS: usize> {
     head: Head,
     knots: [Knot; KNOTS],
@@ -76,6 +83,40 @@ impl<const KNOTS: usize> Rope<KNOTS> {
         }
         self.knots[KNOTS - 1].position
     }
+
+    // Draws the knots like the puzzle text, together with the visited tail cells that
+    // fall in the area spanned by the rope and the starting point.
+    fn render(&self, visited: &Positions) -> String {
+        let mut labelled = vec![(Position(0, 0), 's')];
+        for (i, knot) in self.knots.iter().enumerate().rev() {
+            let label = if KNOTS == 1 {
+                'T'
+            } else {
+                char::from_digit((i + 1) as u32 % 36, 36).unwrap()
+            };
+            labelled.push((knot.position, label));
+        }
+        labelled.push((self.head.position, 'H'));
+        let min_x = labelled.iter().map(|(p, _)| p.0).min().unwrap();
+        let max_x = labelled.iter().map(|(p, _)| p.0).max().unwrap();
+        let min_y = labelled.iter().map(|(p, _)| p.1).min().unwrap();
+        let max_y = labelled.iter().map(|(p, _)| p.1).max().unwrap();
+        let mut grid = vec![vec!['.'; (max_y - min_y + 1) as usize]; (max_x - min_x + 1) as usize];
+        let in_area =
+            |p: &&Position| (min_x..=max_x).contains(&p.0) && (min_y..=max_y).contains(&p.1);
+        let cells = visited
+            .iter()
+            .filter(in_area)
+            .map(|&p| (p, '#'))
+            .chain(labelled);
+        for (Position(x, y), label) in cells {
+            grid[(max_x - x) as usize][(y - min_y) as usize] = label;
+        }
+        grid.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 impl Knot {
@@ -84,19 +125,19 @@ impl Knot {
         let delta_y = y - self.position.1;
         if delta_x == 0 {
             if delta_y.abs() > 1 {
-                self.position.1 += 1 * delta_y.signum();
+                self.position.1 += delta_y.signum();
             }
             return;
         }
         if delta_y == 0 {
             if delta_x.abs() > 1 {
-                self.position.0 += 1 * delta_x.signum();
+                self.position.0 += delta_x.signum();
             }
             return;
         }
         if delta_x.abs() > 1 || delta_y.abs() > 1 {
-            self.position.0 += 1 * delta_x.signum();
-            self.position.1 += 1 * delta_y.signum();
+            self.position.0 += delta_x.signum();
+            self.position.1 += delta_y.signum();
         }
     }
 }
@@ -130,27 +171,127 @@ fn parse_input() -> Moves {
         .collect()
 }
 
-fn generic_solution<const KNOTS: usize>() -> u64 {
+fn visited_tail_positions<const KNOTS: usize>(moves: Moves) -> Positions {
     let mut rope = Rope::<KNOTS>::new();
     let mut tail_positions: Positions = HashSet::from([Position(0, 0)]);
-    let moves = parse_input();
     for Move { direction, amount } in moves {
         for _ in 0..amount {
             let tail_position = rope.update(direction);
             tail_positions.insert(tail_position);
         }
+        trace_event!(
+            Trace,
+            "move done",
+            direction = format!("{:?}", direction),
+            amount = amount,
+            head = format!("{:?}", rope.head.position),
+            visited = tail_positions.len()
+        );
     }
-    tail_positions.len() as u64
+    trace_event!(
+        Debug,
+        "tail visited",
+        knots = KNOTS,
+        cells = tail_positions.len()
+    );
+    tail_positions
 }
 
-fn solution_part_1() -> u64 {
+// Up is drawn towards the top of the image, the starting cell is red.
+fn positions_to_image(positions: &Positions) -> Image {
+    let min_x = positions.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = positions.iter().map(|p| p.0).max().unwrap_or(0);
+    let min_y = positions.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = positions.iter().map(|p| p.1).max().unwrap_or(0);
+    let to_pixel = |&Position(x, y): &Position| ((max_x - x) as usize, (y - min_y) as usize);
+    let mut image = Image::new(
+        (max_x - min_x + 1) as usize,
+        (max_y - min_y + 1) as usize,
+        Rgb::BLACK,
+    );
+    for position in positions {
+        let (row, col) = to_pixel(position);
+        image.set(row, col, Rgb::WHITE);
+    }
+    if positions.contains(&Position(0, 0)) {
+        let (row, col) = to_pixel(&Position(0, 0));
+        image.set(row, col, Rgb::RED);
+    }
+    image
+}
+
+fn generic_solution<const KNOTS: usize>() -> u64 {
+    visited_tail_positions::<KNOTS>(parse_input()).len() as u64
+}
+
+pub fn solution_part_1() -> u64 {
     generic_solution::<1>()
 }
 
-fn solution_part_2() -> u64 {
+pub fn solution_part_2() -> u64 {
     generic_solution::<9>()
 }
 
+pub fn export_image<const KNOTS: usize>(path: &str, format: ImageFormat) -> io::Result<()> {
+    positions_to_image(&visited_tail_positions::<KNOTS>(parse_input())).save(path, format)
+}
+
+#[derive(Clone)]
+pub struct RopeSimulation<const KNOTS: usize> {
+    rope: Rope<KNOTS>,
+    moves: Moves,
+    next_move: usize,
+    remaining: i64,
+    visited: Positions,
+}
+
+impl<const KNOTS: usize> Simulation for RopeSimulation<KNOTS> {
+    // A step moves the head by a single cell.
+    fn step(&mut self) -> bool {
+        while self.remaining == 0 {
+            if self.next_move == self.moves.len() {
+                return false;
+            }
+            self.remaining = self.moves[self.next_move].amount;
+            self.next_move += 1;
+        }
+        let direction = self.moves[self.next_move - 1].direction;
+        self.visited.insert(self.rope.update(direction));
+        self.remaining -= 1;
+        true
+    }
+
+    fn frame(&self) -> String {
+        self.rope.render(&self.visited)
+    }
+
+    fn inspect(&self) -> String {
+        let mut lines = vec![format!("head {:?}", self.rope.head.position)];
+        for (i, knot) in self.rope.knots.iter().enumerate() {
+            lines.push(format!("knot {} {:?}", i + 1, knot.position));
+        }
+        match self.moves.get(self.next_move.wrapping_sub(1)) {
+            Some(Move { direction, amount }) if self.remaining > 0 => lines.push(format!(
+                "move {} {:?} {}, {} steps left",
+                self.next_move, direction, amount, self.remaining
+            )),
+            _ => lines.push(format!("move {} done", self.next_move)),
+        }
+        lines.push(format!("visited by tail: {}", self.visited.len()));
+        lines.join("\n")
+    }
+}
+
+pub fn simulation<const KNOTS: usize>() -> RopeSimulation<KNOTS> {
+    RopeSimulation {
+        rope: Rope::new(),
+        moves: parse_input(),
+        next_move: 0,
+        remaining: 0,
+        visited: HashSet::from([Position(0, 0)]),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::problem_9::*;
@@ -164,4 +305,30 @@ mod tests {
     fn problem_9_solution_part_2_test() {
         println!("problem 9 solution 2: {}", solution_part_2());
     }
+
+    #[test]
+    fn problem_9_simulation_test() {
+        let mut simulation = RopeSimulation::<9> {
+            rope: Rope::new(),
+            moves: vec![Move {
+                direction: Direction::Right,
+                amount: 4,
+            }],
+            next_move: 0,
+            remaining: 0,
+            visited: HashSet::from([Position(0, 0)]),
+        };
+        while simulation.step() {}
+        assert_eq!(simulation.frame(), "4321H");
+    }
+
+    #[test]
+    fn problem_9_positions_to_image_test() {
+        let positions = HashSet::from([Position(0, 0), Position(1, 0), Position(1, 2)]);
+        let image = positions_to_image(&positions);
+        assert_eq!(image.get(1, 0), Rgb::RED);
+        assert_eq!(image.get(0, 0), Rgb::WHITE);
+        assert_eq!(image.get(0, 1), Rgb::BLACK);
+        assert_eq!(image.get(0, 2), Rgb::WHITE);
+    }
 }
@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+pub trait Simulation {
+    // Advances the simulation by one step. Returns false once it is over.
+    fn step(&mut self) -> bool;
+
+    fn frame(&self) -> String;
+
+    // Full state, for when the frame alone is not enough to understand what happened.
+    fn inspect(&self) -> String {
+        self.frame()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub step: usize,
+    pub text: String,
+}
+
+// Keeps the frames from `first` to `last` included, one every `every` steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameFilter {
+    first: usize,
+    last: Option<usize>,
+    every: usize,
+}
+
+impl Default for FrameFilter {
+    fn default() -> Self {
+        Self {
+            first: 0,
+            last: None,
+            every: 1,
+        }
+    }
+}
+
+impl FrameFilter {
+    pub fn new(first: usize, last: Option<usize>, every: usize) -> Result<Self, String> {
+        if every == 0 {
+            return Err("The frame interval must be at least 1".to_owned());
+        }
+        Ok(Self { first, last, every })
+    }
+
+    fn accepts(&self, step: usize) -> bool {
+        step >= self.first && !self.is_past(step) && (step - self.first).is_multiple_of(self.every)
+    }
+
+    fn is_past(&self, step: usize) -> bool {
+        self.last.is_some_and(|last| step > last)
+    }
+}
+
+// Receives the frames as they are recorded.
+pub trait FrameSink {
+    fn write_frame(&mut self, frame: Frame) -> io::Result<()>;
+}
+
+impl FrameSink for Vec<Frame> {
+    fn write_frame(&mut self, frame: Frame) -> io::Result<()> {
+        self.push(frame);
+        Ok(())
+    }
+}
+
+// Prints each frame over the previous one, waiting between frames.
+pub struct Player<W> {
+    delay: Duration,
+    out: W,
+}
+
+impl<W: Write> Player<W> {
+    pub fn new(fps: f64, out: W) -> Self {
+        Self {
+            delay: Duration::from_secs_f64(1.0 / fps),
+            out,
+        }
+    }
+}
+
+impl<W: Write> FrameSink for Player<W> {
+    fn write_frame(&mut self, frame: Frame) -> io::Result<()> {
+        // Clear the screen and move the cursor to the top left corner
+        write!(self.out, "\x1b[2J\x1b[H")?;
+        writeln!(self.out, "step {}\n{}", frame.step, frame.text)?;
+        self.out.flush()?;
+        thread::sleep(self.delay);
+        Ok(())
+    }
+}
+
+// Writes each frame to its own file in a directory, created with the first frame.
+pub struct Dumper {
+    directory: PathBuf,
+    created: bool,
+}
+
+impl Dumper {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_owned(),
+            created: false,
+        }
+    }
+}
+
+impl FrameSink for Dumper {
+    fn write_frame(&mut self, frame: Frame) -> io::Result<()> {
+        if !self.created {
+            fs::create_dir_all(&self.directory)?;
+            self.created = true;
+        }
+        let path = self.directory.join(format!("frame_{:06}.txt", frame.step));
+        fs::write(path, frame.text)
+    }
+}
+
+// Step 0 is the initial state. Frames are only rendered when the filter keeps them,
+// and go to the sink right away.
+pub fn record(
+    simulation: &mut impl Simulation,
+    filter: &FrameFilter,
+    sink: &mut impl FrameSink,
+) -> io::Result<()> {
+    let mut step = 0;
+    loop {
+        if filter.accepts(step) {
+            sink.write_frame(Frame {
+                step,
+                text: simulation.frame(),
+            })?;
+        }
+        if filter.is_past(step + 1) || !simulation.step() {
+            return Ok(());
+        }
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::recording::*;
+
+    struct Counter {
+        value: usize,
+        limit: usize,
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            if self.value == self.limit {
+                return false;
+            }
+            self.value += 1;
+            true
+        }
+
+        fn frame(&self) -> String {
+            self.value.to_string()
+        }
+    }
+
+    fn steps(frames: &[Frame]) -> Vec<usize> {
+        frames.iter().map(|frame| frame.step).collect()
+    }
+
+    fn record_frames(simulation: &mut impl Simulation, filter: &FrameFilter) -> Vec<Frame> {
+        let mut frames = vec![];
+        record(simulation, filter, &mut frames).unwrap();
+        frames
+    }
+
+    #[test]
+    fn recording_record_test() {
+        let mut counter = Counter { value: 0, limit: 5 };
+        let frames = record_frames(&mut counter, &FrameFilter::default());
+        assert_eq!(steps(&frames), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(frames[3].text, "3");
+
+        let mut counter = Counter {
+            value: 0,
+            limit: 100,
+        };
+        let filter = FrameFilter::new(2, Some(9), 3).unwrap();
+        let frames = record_frames(&mut counter, &filter);
+        assert_eq!(steps(&frames), vec![2, 5, 8]);
+        assert_eq!(counter.value, 9);
+        assert!(FrameFilter::new(0, None, 0).is_err());
+    }
+
+    #[test]
+    fn recording_dump_test() {
+        let directory = std::env::temp_dir().join(format!(
+            "aoc2022_recording_dump_test_{}",
+            std::process::id()
+        ));
+        let mut dumper = Dumper::new(&directory);
+        let counter = &mut Counter { value: 0, limit: 2 };
+        let recorded = record(counter, &FrameFilter::default(), &mut dumper);
+        let text = fs::read_to_string(directory.join("frame_000002.txt"));
+        fs::remove_dir_all(&directory).unwrap();
+        recorded.unwrap();
+        assert_eq!(text.unwrap(), "2");
+    }
+}
@@ -0,0 +1,158 @@
+#![allow(dead_code)]
+
+use std::{
+    env,
+    fmt::{Display, Write},
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+pub const ENV_VAR: &str = "AOC_TRACE";
+
+const OFF: u8 = 0;
+const UNINITIALIZED: u8 = u8::MAX;
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(UNINITIALIZED);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+// `None` turns tracing off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelFilter(pub Option<Level>);
+
+impl FromStr for LevelFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let level = match s.to_ascii_lowercase().as_str() {
+            "off" | "" => None,
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => return Err(format!("Invalid trace level {}", s)),
+        };
+        Ok(Self(level))
+    }
+}
+
+pub fn set_max_level(LevelFilter(level): LevelFilter) {
+    MAX_LEVEL.store(level.map_or(OFF, |l| l as u8), Ordering::Relaxed);
+}
+
+// An invalid value turns tracing off with a warning, so that a typo is not mistaken
+// for an absence of events.
+fn filter_from_env() -> LevelFilter {
+    let Ok(value) = env::var(ENV_VAR) else {
+        return LevelFilter(None);
+    };
+    value.parse().unwrap_or_else(|e| {
+        eprintln!("warning: {} in {}, tracing is off", e, ENV_VAR);
+        LevelFilter(None)
+    })
+}
+
+// The environment variable is only read the first time, unless the level has been
+// set explicitly before.
+#[inline]
+pub fn enabled(level: Level) -> bool {
+    let mut max_level = MAX_LEVEL.load(Ordering::Relaxed);
+    if max_level == UNINITIALIZED {
+        set_max_level(filter_from_env());
+        max_level = MAX_LEVEL.load(Ordering::Relaxed);
+    }
+    level as u8 <= max_level
+}
+
+fn push_value(line: &mut String, value: &str) {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        write!(line, "{:?}", value).unwrap();
+    } else {
+        line.push_str(value);
+    }
+}
+
+// One `key=value` line per event, values are quoted only when needed.
+pub fn format_event(
+    level: Level,
+    target: &str,
+    message: &str,
+    fields: &[(&str, &dyn Display)],
+) -> String {
+    let target = target.strip_prefix("aoc2022::").unwrap_or(target);
+    let mut line = format!("level={} target={} msg=", level.name(), target);
+    push_value(&mut line, message);
+    for (key, value) in fields {
+        write!(line, " {}=", key).unwrap();
+        push_value(&mut line, &value.to_string());
+    }
+    line
+}
+
+pub fn write_event(level: Level, target: &str, message: &str, fields: &[(&str, &dyn Display)]) {
+    eprintln!("{}", format_event(level, target, message, fields));
+}
+
+// Arguments are only evaluated when the level is enabled, so a disabled trace point
+// costs a relaxed atomic load.
+macro_rules! trace_event {
+    ($level:ident, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($crate::trace::Level::$level) {
+            $crate::trace::write_event(
+                $crate::trace::Level::$level,
+                module_path!(),
+                $message,
+                &[$((stringify!($key), &$value as &dyn std::fmt::Display)),*],
+            );
+        }
+    };
+}
+
+pub(crate) use trace_event;
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::*;
+
+    #[test]
+    fn trace_format_event_test() {
+        let line = format_event(
+            Level::Debug,
+            "aoc2022::problem_12",
+            "node popped",
+            &[("frontier", &12), ("name", &"a b"), ("path", &"/a")],
+        );
+        assert_eq!(
+            line,
+            r#"level=debug target=problem_12 msg="node popped" frontier=12 name="a b" path=/a"#
+        );
+    }
+
+    #[test]
+    fn trace_level_filter_test() {
+        assert_eq!("DEBUG".parse(), Ok(LevelFilter(Some(Level::Debug))));
+        assert_eq!("off".parse(), Ok(LevelFilter(None)));
+        assert!("loud".parse::<LevelFilter>().is_err());
+        assert!(Level::Error < Level::Trace);
+    }
+}
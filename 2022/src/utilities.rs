@@ -1,3 +1,5 @@
+pub mod number_theory;
+
 use std::fs;
 
 use num_traits::cast::cast;
@@ -0,0 +1,184 @@
+#![allow(dead_code)]
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberTheoryError {
+    Overflow,
+    ZeroModulus,
+    NotInvertible,
+    NoSolution,
+}
+
+impl fmt::Display for NumberTheoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "overflow"),
+            Self::ZeroModulus => write!(f, "modulus is zero"),
+            Self::NotInvertible => write!(f, "value is not invertible"),
+            Self::NoSolution => write!(f, "congruences have no solution"),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, NumberTheoryError>;
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// 0 when either value is 0.
+pub fn lcm(a: u64, b: u64) -> Result<u64> {
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    (a / gcd(a, b))
+        .checked_mul(b)
+        .ok_or(NumberTheoryError::Overflow)
+}
+
+pub fn gcd_all(values: impl IntoIterator<Item = u64>) -> u64 {
+    values.into_iter().fold(0, gcd)
+}
+
+// 1 for no values.
+pub fn lcm_all(values: impl IntoIterator<Item = u64>) -> Result<u64> {
+    values.into_iter().try_fold(1, lcm)
+}
+
+// (g, x, y) with a x + b y = g = gcd(a, b), and g >= 0.
+pub fn extended_gcd(a: i64, b: i64) -> Result<(i64, i64, i64)> {
+    let (g, x, y) = extended_gcd_wide(a as i128, b as i128);
+    let narrow = |n: i128| i64::try_from(n).map_err(|_| NumberTheoryError::Overflow);
+    Ok((narrow(g)?, narrow(x)?, narrow(y)?))
+}
+
+fn extended_gcd_wide(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// The x in [0, modulus) with a x = 1 (mod modulus).
+pub fn mod_inverse(a: u64, modulus: u64) -> Result<u64> {
+    if modulus == 0 {
+        return Err(NumberTheoryError::ZeroModulus);
+    }
+    let (g, x, _) = extended_gcd_wide(a as i128, modulus as i128);
+    if g != 1 {
+        return Err(NumberTheoryError::NotInvertible);
+    }
+    Ok(x.rem_euclid(modulus as i128) as u64)
+}
+
+pub fn mod_pow(base: u64, mut exponent: u64, modulus: u64) -> Result<u64> {
+    if modulus == 0 {
+        return Err(NumberTheoryError::ZeroModulus);
+    }
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    Ok(result as u64)
+}
+
+// Solves x = residue (mod modulus) for every pair, the moduli need not be coprime.
+// Returns the smallest solution and the lcm of the moduli, which gives all the
+// others.
+pub fn chinese_remainder(congruences: impl IntoIterator<Item = (u64, u64)>) -> Result<(u64, u64)> {
+    congruences
+        .into_iter()
+        .try_fold((0, 1), |(x, m), (residue, modulus)| {
+            if modulus == 0 {
+                return Err(NumberTheoryError::ZeroModulus);
+            }
+            let g = gcd(m, modulus);
+            let residue = residue % modulus;
+            let diff = residue as i128 - x as i128;
+            if diff % g as i128 != 0 {
+                return Err(NumberTheoryError::NoSolution);
+            }
+            let combined = lcm(m, modulus)?;
+            let step = modulus / g;
+            // x + m k satisfies the new congruence when (m / g) k = diff / g (mod step)
+            let k = match step {
+                1 => 0,
+                _ => {
+                    let inverse = mod_inverse((m / g) % step, step)? as u128;
+                    let quotient = (diff / g as i128).rem_euclid(step as i128) as u128;
+                    (quotient * inverse % step as u128) as i128
+                }
+            };
+            let x = (x as i128 + m as i128 * k) % combined as i128;
+            Ok((x as u64, combined))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utilities::number_theory::*;
+
+    #[test]
+    fn number_theory_gcd_lcm_test() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd_all([12, 18, 8]), 2);
+        assert_eq!(lcm_all([4, 6, 4]), Ok(12));
+        assert_eq!(lcm_all([]), Ok(1));
+        assert_eq!(lcm_all([3, 0]), Ok(0));
+        assert_eq!(lcm_all([1 << 40, (1 << 30) * 3, 5 << 20]), Ok(15 << 40));
+        assert_eq!(
+            lcm_all([u64::MAX, u64::MAX - 1]),
+            Err(NumberTheoryError::Overflow)
+        );
+    }
+
+    #[test]
+    fn number_theory_modular_test() {
+        assert_eq!(extended_gcd(240, 46), Ok((2, -9, 47)));
+        assert_eq!(extended_gcd(-4, 6), Ok((2, 1, 1)));
+        assert_eq!(extended_gcd(i64::MIN, 0), Err(NumberTheoryError::Overflow));
+        assert_eq!(mod_inverse(3, 11), Ok(4));
+        assert_eq!(mod_inverse(6, 9), Err(NumberTheoryError::NotInvertible));
+        assert_eq!(mod_inverse(6, 0), Err(NumberTheoryError::ZeroModulus));
+        assert_eq!(mod_pow(4, 13, 497), Ok(445));
+        assert_eq!(mod_pow(u64::MAX, u64::MAX, u64::MAX - 1), Ok(1));
+        assert_eq!(mod_pow(5, 0, 1), Ok(0));
+    }
+
+    #[test]
+    fn number_theory_chinese_remainder_test() {
+        assert_eq!(chinese_remainder([(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        // Moduli sharing a factor
+        assert_eq!(chinese_remainder([(3, 4), (5, 6)]), Ok((11, 12)));
+        assert_eq!(
+            chinese_remainder([(1, 4), (2, 6)]),
+            Err(NumberTheoryError::NoSolution)
+        );
+        assert_eq!(chinese_remainder([]), Ok((0, 1)));
+        assert_eq!(
+            chinese_remainder([(1, u64::MAX), (0, u64::MAX - 1)]),
+            Err(NumberTheoryError::Overflow)
+        );
+    }
+}